@@ -1,5 +1,7 @@
 mod marker;
 mod particle;
+mod rigid_body;
 
 pub use marker::*;
 pub use particle::*;
+pub use rigid_body::*;
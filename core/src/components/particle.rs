@@ -1,16 +1,17 @@
 use specs::prelude::*;
+use std::sync::Arc;
 
 use crate::utils::*;
 
 #[derive(Copy, Clone)]
-pub struct ParticleMass(pub f32);
+pub struct ParticleMass(pub Float);
 
 impl ParticleMass {
-  pub fn new(m: f32) -> Self {
+  pub fn new(m: Float) -> Self {
     Self(m)
   }
 
-  pub fn get(&self) -> f32 {
+  pub fn get(&self) -> Float {
     self.0
   }
 }
@@ -20,14 +21,14 @@ impl Component for ParticleMass {
 }
 
 #[derive(Copy, Clone)]
-pub struct ParticleVolume(pub f32);
+pub struct ParticleVolume(pub Float);
 
 impl ParticleVolume {
-  pub fn new(v: f32) -> Self {
+  pub fn new(v: Float) -> Self {
     Self(v)
   }
 
-  pub fn get(&self) -> f32 {
+  pub fn get(&self) -> Float {
     self.0
   }
 }
@@ -78,6 +79,69 @@ impl Component for ParticleVelocity {
   type Storage = VecStorage<Self>;
 }
 
+/// The affine velocity matrix `C` of a particle used by APIC transfer
+#[derive(Copy, Clone)]
+pub struct ParticleAffineVelocity(pub Matrix3f);
+
+impl ParticleAffineVelocity {
+  pub fn new(c: Matrix3f) -> Self {
+    Self(c)
+  }
+
+  pub fn zeros() -> Self {
+    Self(Matrix3f::zeros())
+  }
+
+  pub fn get(&self) -> Matrix3f {
+    self.0
+  }
+
+  pub fn set(&mut self, c: Matrix3f) {
+    self.0 = c;
+  }
+}
+
+impl Component for ParticleAffineVelocity {
+  type Storage = VecStorage<Self>;
+}
+
+/// Per particle override of the world's `FlipRatio`
+#[derive(Copy, Clone)]
+pub struct ParticleFlipRatio(pub Float);
+
+impl ParticleFlipRatio {
+  pub fn new(ratio: Float) -> Self {
+    Self(ratio)
+  }
+
+  pub fn get(&self) -> Float {
+    self.0
+  }
+}
+
+impl Component for ParticleFlipRatio {
+  type Storage = VecStorage<Self>;
+}
+
+/// The body a particle belongs to. Every call to `put_region` creates a new body. With body
+/// contact enabled, each body gets its own velocity field on the grid
+#[derive(Copy, Clone)]
+pub struct ParticleBody(pub usize);
+
+impl ParticleBody {
+  pub fn new(body: usize) -> Self {
+    Self(body)
+  }
+
+  pub fn get(&self) -> usize {
+    self.0
+  }
+}
+
+impl Component for ParticleBody {
+  type Storage = VecStorage<Self>;
+}
+
 #[derive(Copy, Clone)]
 pub struct ParticleDeformation {
   /// F_E, elastic deformation gradient
@@ -87,23 +151,36 @@ pub struct ParticleDeformation {
   pub f_plastic: Matrix3f,
 
   /// mu_0, one of the initial Lame parameters
-  pub mu: f32,
+  pub mu: Float,
 
   /// lambda_0, the other one of the initial Lame parameters
-  pub lambda: f32,
+  pub lambda: Float,
 
   /// Compression limit
-  pub theta_c: f32,
+  pub theta_c: Float,
 
   /// Stretch limit
-  pub theta_s: f32,
+  pub theta_s: Float,
 
   /// Hardening Factor, 0 for no hardening
-  pub hardening: f32,
+  pub hardening: Float,
+
+  /// Liquid fraction from 0 (solid) to 1 (liquid), see `ParticleMelting`. The shear
+  /// modulus vanishes as the particle melts
+  pub liquid_fraction: Float,
+
+  /// Thermal softening coefficient, 0 for Lame parameters that do not depend on the temperature
+  pub thermal_softening: Float,
+
+  /// The temperature at which the Lame parameters are mu_0 and lambda_0
+  pub reference_temperature: Float,
+
+  /// The current temperature of the particle, kept up to date from its `ParticleTemperature`
+  pub temperature: Float,
 }
 
 impl ParticleDeformation {
-  pub fn new(youngs_modulus: f32, poisson_ratio: f32, theta_c: f32, theta_s: f32, hardening: f32) -> Self {
+  pub fn new(youngs_modulus: Float, poisson_ratio: Float, theta_c: Float, theta_s: Float, hardening: Float) -> Self {
     Self {
       f_elastic: Matrix3f::identity(),
       f_plastic: Matrix3f::identity(),
@@ -112,12 +189,16 @@ impl ParticleDeformation {
       theta_c,
       theta_s,
       hardening,
+      liquid_fraction: 0.0,
+      thermal_softening: 0.0,
+      reference_temperature: 0.0,
+      temperature: 0.0,
     }
   }
 
   /// E_0: Initial Young's Modulus
   /// nu: Poisson Ratio
-  pub fn elastic(youngs_modulus: f32, poisson_ratio: f32) -> Self {
+  pub fn elastic(youngs_modulus: Float, poisson_ratio: Float) -> Self {
     Self {
       f_elastic: Matrix3f::identity(),
       f_plastic: Matrix3f::identity(),
@@ -126,6 +207,10 @@ impl ParticleDeformation {
       theta_c: 1.0,
       theta_s: 1.0,
       hardening: 0.0,
+      liquid_fraction: 0.0,
+      thermal_softening: 0.0,
+      reference_temperature: 0.0,
+      temperature: 0.0,
     }
   }
 
@@ -140,14 +225,111 @@ impl ParticleDeformation {
       theta_c: 0.025,
       theta_s: 0.0075,
       hardening: 10.0,
+      liquid_fraction: 0.0,
+      thermal_softening: 0.0,
+      reference_temperature: 0.0,
+      temperature: 0.0,
     }
   }
 
-  fn mu(youngs_modulus: f32, poisson_ratio: f32) -> f32 {
+  /// Soften (or stiffen below `reference_temperature`) the material with the temperature,
+  /// see `lame_parameters`
+  pub fn with_thermal_softening(mut self, thermal_softening: Float, reference_temperature: Float) -> Self {
+    self.thermal_softening = thermal_softening;
+    self.reference_temperature = reference_temperature;
+    self.temperature = reference_temperature;
+    self
+  }
+
+  /// The total deformation gradient $F = F_E F_P$
+  pub fn deformation_gradient(&self) -> Matrix3f {
+    self.f_elastic * self.f_plastic
+  }
+
+  /// The current Lame parameters mu and lambda. Following
+  /// [Stomakhin et al. 2013](https://www.math.ucla.edu/~jteran/papers/SSCTS13.pdf), they harden under
+  /// plastic compression (and soften under plastic stretch) as
+  ///
+  /// $$\mu = \mu_0 e^{\xi (1 - J_P)},\ \lambda = \lambda_0 e^{\xi (1 - J_P)}$$
+  ///
+  /// Both are scaled by the thermal factor $e^{-\alpha (T - T_0)}$, and the shear modulus is
+  /// further scaled by the solid fraction of melting particles. Lambda is kept as the liquid
+  /// still resists compression, as in Stomakhin et al. 2014, "Augmented MPM for Phase-Change
+  /// and Varied Materials".
+  pub fn lame_parameters(&self) -> (Float, Float) {
+    let factor = self.hardening_factor() * self.thermal_factor();
+    (self.mu * factor * (1.0 - self.liquid_fraction), self.lambda * factor)
+  }
+
+  /// The thermal factor $e^{-\alpha (T - T_0)}$ of the Lame parameters
+  pub fn thermal_factor(&self) -> Float {
+    if self.thermal_softening == 0.0 {
+      1.0
+    } else {
+      (-self.thermal_softening * (self.temperature - self.reference_temperature)).exp()
+    }
+  }
+
+  /// The hardening factor $e^{\xi (1 - J_P)}$. Degenerate plastic deformations give no hardening
+  pub fn hardening_factor(&self) -> Float {
+    let j_p = self.f_plastic.determinant();
+    if self.hardening == 0.0 || !j_p.is_finite() || j_p <= 0.0 {
+      1.0
+    } else {
+      (self.hardening * (1.0 - j_p)).exp()
+    }
+  }
+
+  /// Drop the shear part of the elastic deformation gradient, keeping its volume change
+  /// up to `max_j`. The rest is moved into $F_P$ so that $F$ stays the same
+  pub fn remove_elastic_shear(&mut self, max_j: Float) {
+    let f = self.deformation_gradient();
+    let j_e = self.f_elastic.determinant();
+    if j_e.is_finite() && j_e > 0.0 {
+      self.f_elastic = j_e.min(max_j).cbrt() * Matrix3f::identity();
+      self.f_plastic = self.f_elastic.try_inverse().unwrap_or(Matrix3f::identity()) * f;
+    }
+  }
+
+  /// The first Piola-Kirchhoff stress of the given elastic deformation gradient
+  /// under the given model, using the current Lame parameters
+  pub fn first_piola_kirchhoff(&self, model: &dyn ConstitutiveModel, f_e: &Matrix3f) -> Matrix3f {
+    let (mu, lambda) = self.lame_parameters();
+    model.first_piola_kirchhoff(f_e, mu, lambda)
+  }
+
+  /// The differential of the first Piola-Kirchhoff stress in direction `df`, using the
+  /// current Lame parameters. Falls back to finite differences when the model does not
+  /// provide it
+  pub fn first_piola_kirchhoff_differential(
+    &self,
+    model: &dyn ConstitutiveModel,
+    f_e: &Matrix3f,
+    df: &Matrix3f,
+  ) -> Matrix3f {
+    let (mu, lambda) = self.lame_parameters();
+    model
+      .first_piola_kirchhoff_differential(f_e, df, mu, lambda)
+      .unwrap_or_else(|| {
+        let eps = 1e-3 / df.norm().max(1e-12);
+        let p1 = model.first_piola_kirchhoff(&(f_e + eps * df), mu, lambda);
+        let p0 = model.first_piola_kirchhoff(&(f_e - eps * df), mu, lambda);
+        (p1 - p0) / (2.0 * eps)
+      })
+  }
+
+  /// The elastic energy density of the given elastic deformation gradient under the given
+  /// model, using the current Lame parameters
+  pub fn energy_density(&self, model: &dyn ConstitutiveModel, f_e: &Matrix3f) -> Float {
+    let (mu, lambda) = self.lame_parameters();
+    model.energy_density(f_e, mu, lambda)
+  }
+
+  fn mu(youngs_modulus: Float, poisson_ratio: Float) -> Float {
     youngs_modulus / (2.0 * (1.0 + poisson_ratio))
   }
 
-  fn lambda(youngs_modulus: f32, poisson_ratio: f32) -> f32 {
+  fn lambda(youngs_modulus: Float, poisson_ratio: Float) -> Float {
     youngs_modulus * poisson_ratio / ((1.0 + poisson_ratio) * (1.0 - 2.0 * poisson_ratio))
   }
 }
@@ -155,3 +337,404 @@ impl ParticleDeformation {
 impl Component for ParticleDeformation {
   type Storage = VecStorage<Self>;
 }
+
+/// The elastic model of a deformable particle. Particles with `ParticleDeformation`
+/// but without this component use `FixedCorotated`.
+#[derive(Clone)]
+pub struct ParticleConstitutiveModel(pub Arc<dyn ConstitutiveModel>);
+
+impl ParticleConstitutiveModel {
+  pub fn new<M: ConstitutiveModel + 'static>(model: M) -> Self {
+    Self(Arc::new(model))
+  }
+
+  pub fn get(&self) -> &dyn ConstitutiveModel {
+    &*self.0
+  }
+}
+
+impl Component for ParticleConstitutiveModel {
+  type Storage = VecStorage<Self>;
+}
+
+/// The plasticity model of a deformable particle along with its plastic state. Particles with
+/// `ParticleDeformation` but without this component use `SnowPlasticity` with their own
+/// `theta_c` and `theta_s`.
+#[derive(Clone)]
+pub struct ParticlePlasticity {
+  pub model: Arc<dyn PlasticityModel>,
+
+  /// The scalar plastic state of the model, starting at 0
+  pub state: Float,
+}
+
+impl ParticlePlasticity {
+  pub fn new<M: PlasticityModel + 'static>(model: M) -> Self {
+    Self {
+      model: Arc::new(model),
+      state: 0.0,
+    }
+  }
+
+  /// Drucker-Prager sand with the given friction angle in degrees
+  pub fn drucker_prager(friction_angle: Float) -> Self {
+    Self::new(DruckerPrager::new(friction_angle))
+  }
+
+  /// Von Mises metal with the given yield stress and hardening modulus
+  pub fn von_mises(yield_stress: Float, hardening_modulus: Float) -> Self {
+    Self::new(VonMises::new(yield_stress).with_hardening(hardening_modulus))
+  }
+
+  /// Cam-Clay with the given critical state slope, tensile strength ratio and hardening
+  pub fn cam_clay(m: Float, beta: Float, hardening: Float) -> Self {
+    Self::new(CamClay::new(m, beta, hardening))
+  }
+
+  /// Set the initial plastic state, e.g. a negative $\log J_P$ for precompacted Cam-Clay
+  pub fn with_state(mut self, state: Float) -> Self {
+    self.state = state;
+    self
+  }
+
+  pub fn get(&self) -> &dyn PlasticityModel {
+    &*self.model
+  }
+}
+
+impl Component for ParticlePlasticity {
+  type Storage = VecStorage<Self>;
+}
+
+/// A weakly compressible fluid particle, which only tracks its volume ratio $J$ instead of a
+/// full `ParticleDeformation`. The pressure is given by the Tait equation of state
+///
+/// $$p = \frac{K}{\gamma} (J^{-\gamma} - 1)$$
+#[derive(Copy, Clone)]
+pub struct ParticleFluid {
+  /// J, the ratio between the current and the initial volume
+  pub j: Float,
+
+  /// K, the bulk modulus
+  pub bulk_modulus: Float,
+
+  /// The exponent of the equation of state
+  pub gamma: Float,
+
+  /// Dynamic viscosity, 0 for inviscid fluid
+  pub viscosity: Float,
+}
+
+impl ParticleFluid {
+  pub fn new(bulk_modulus: Float, gamma: Float) -> Self {
+    Self {
+      j: 1.0,
+      bulk_modulus,
+      gamma,
+      viscosity: 0.0,
+    }
+  }
+
+  /// Water with a bulk modulus low enough for practical time steps
+  pub fn water() -> Self {
+    Self::new(100000.0, 7.0).with_viscosity(0.001)
+  }
+
+  pub fn with_viscosity(mut self, viscosity: Float) -> Self {
+    self.viscosity = viscosity;
+    self
+  }
+
+  /// The pressure from the equation of state
+  pub fn pressure(&self) -> Float {
+    self.bulk_modulus / self.gamma * (self.j.powf(-self.gamma) - 1.0)
+  }
+
+  /// The Cauchy stress $\sigma = -p I + \eta (\nabla v + \nabla v^T)$ given the velocity gradient
+  pub fn cauchy_stress(&self, grad_vp: &Matrix3f) -> Matrix3f {
+    -self.pressure() * Matrix3f::identity() + self.viscosity * (grad_vp + grad_vp.transpose())
+  }
+
+  /// An isotropic deformation gradient $J^{1/3} I$ with the same volume change, used for
+  /// particle domains
+  pub fn deformation_gradient(&self) -> Matrix3f {
+    self.j.cbrt() * Matrix3f::identity()
+  }
+}
+
+impl Component for ParticleFluid {
+  type Storage = VecStorage<Self>;
+}
+
+/// Viscoelastic behavior of a deformable particle, for gooey materials that bounce on short
+/// time scales but flow over time. The deviatoric part of the elastic log strain relaxes
+/// toward rest as a Maxwell element, $\epsilon_{dev} \leftarrow e^{-\Delta t / \tau} \epsilon_{dev}$,
+/// with the relaxed part moved into $F_P$. An optional viscous stress
+/// $\eta (\nabla v + \nabla v^T)$ damps the motion on top of the elastic stress.
+#[derive(Copy, Clone)]
+pub struct ParticleViscoelasticity {
+  /// tau, the relaxation time. Smaller values flow faster
+  pub relaxation_time: Float,
+
+  /// eta, the viscosity of the damping stress
+  pub viscosity: Float,
+}
+
+impl ParticleViscoelasticity {
+  pub fn new(relaxation_time: Float) -> Self {
+    Self {
+      relaxation_time,
+      viscosity: 0.0,
+    }
+  }
+
+  pub fn with_viscosity(mut self, viscosity: Float) -> Self {
+    self.viscosity = viscosity;
+    self
+  }
+
+  /// Relax the elastic singular values over a time step, preserving the volume
+  pub fn relax(&self, sigma: &Vector3f, dt: Float) -> Vector3f {
+    let factor = if self.relaxation_time > 0.0 {
+      (-dt / self.relaxation_time).exp()
+    } else {
+      0.0
+    };
+    let eps = sigma.map(|s| s.max(1e-6).ln());
+    let mean = eps.sum() / 3.0;
+    eps.map(|e| (mean + factor * (e - mean)).exp())
+  }
+
+  /// The viscous Cauchy stress given the velocity gradient
+  pub fn viscous_stress(&self, grad_vp: &Matrix3f) -> Matrix3f {
+    self.viscosity * (grad_vp + grad_vp.transpose())
+  }
+}
+
+impl Component for ParticleViscoelasticity {
+  type Storage = VecStorage<Self>;
+}
+
+/// The criterion driving the damage of a particle, along with its threshold
+#[derive(Copy, Clone, Debug)]
+pub enum DamageCriterion {
+  /// Maximum principal strain of the elastic deformation, $\max_i \sigma_i - 1$
+  Strain(Float),
+
+  /// Maximum principal Cauchy stress
+  Stress(Float),
+}
+
+impl DamageCriterion {
+  pub fn threshold(&self) -> Float {
+    match *self {
+      DamageCriterion::Strain(threshold) | DamageCriterion::Stress(threshold) => threshold,
+    }
+  }
+
+  /// The value of the criterion for a deformable particle under the given model
+  pub fn evaluate(&self, def: &ParticleDeformation, model: &dyn ConstitutiveModel) -> Float {
+    match *self {
+      DamageCriterion::Strain(_) => {
+        let (_, sigma, _) = Math::svd3(&def.f_elastic);
+        sigma.max() - 1.0
+      }
+      DamageCriterion::Stress(_) => {
+        let p = def.first_piola_kirchhoff(model, &def.f_elastic);
+        let cauchy = p * def.f_elastic.transpose() / def.f_elastic.determinant();
+        (0.5 * (cauchy + cauchy.transpose())).symmetric_eigenvalues().max()
+      }
+    }
+  }
+}
+
+/// Continuum damage of a deformable particle. Once the criterion goes beyond its threshold,
+/// the damage grows irreversibly from 0 (intact) to 1 (broken), following
+///
+/// $$d = 1 - \frac{\kappa_0}{\kappa} e^{-(\kappa - \kappa_0) / s}$$
+///
+/// where $\kappa$ is the largest value of the criterion so far, $\kappa_0$ the threshold and
+/// $s$ the softening. The stress of a damaged particle is scaled by $1 - d$ under tension.
+#[derive(Copy, Clone, Debug)]
+pub struct ParticleDamage {
+  /// d, the damage of the particle
+  pub damage: Float,
+
+  /// The criterion driving the damage
+  pub criterion: DamageCriterion,
+
+  /// How gradually the damage grows beyond the threshold, 0 for brittle fracture
+  pub softening: Float,
+
+  /// kappa, the largest value of the criterion so far
+  pub history: Float,
+}
+
+impl ParticleDamage {
+  /// Brittle fracture, fully breaking as soon as the threshold is reached
+  pub fn brittle(criterion: DamageCriterion) -> Self {
+    Self::ductile(criterion, 0.0)
+  }
+
+  /// Ductile fracture, gradually softening beyond the threshold
+  pub fn ductile(criterion: DamageCriterion, softening: Float) -> Self {
+    Self {
+      damage: 0.0,
+      criterion,
+      softening,
+      history: 0.0,
+    }
+  }
+
+  pub fn get(&self) -> Float {
+    self.damage
+  }
+
+  /// Whether the particle is fully broken
+  pub fn is_fractured(&self) -> bool {
+    self.damage >= 1.0
+  }
+
+  /// Update the damage given the current value of the criterion
+  pub fn update(&mut self, value: Float) {
+    self.history = self.history.max(value);
+    let (kappa, kappa_0) = (self.history, self.criterion.threshold());
+    if kappa > kappa_0 {
+      let damage = if self.softening > 0.0 {
+        1.0 - kappa_0 / kappa * (-(kappa - kappa_0) / self.softening).exp()
+      } else {
+        1.0
+      };
+      self.damage = self.damage.max(damage.min(1.0));
+    }
+  }
+
+  /// The factor scaling the stress given the elastic deformation. Only expanding
+  /// particles are weakened so that broken pieces still collide
+  pub fn stress_factor(&self, f_e: &Matrix3f) -> Float {
+    if f_e.determinant() > 1.0 {
+      1.0 - self.damage
+    } else {
+      1.0
+    }
+  }
+}
+
+impl Component for ParticleDamage {
+  type Storage = VecStorage<Self>;
+}
+
+/// The temperature of a particle along with its thermal properties. It is transferred to
+/// the grid where heat diffuses, and back to the particle every step.
+#[derive(Copy, Clone)]
+pub struct ParticleTemperature {
+  pub temperature: Float,
+
+  /// c, the specific heat capacity
+  pub heat_capacity: Float,
+
+  /// k, the thermal conductivity
+  pub conductivity: Float,
+}
+
+impl ParticleTemperature {
+  /// Panics if the heat capacity is not positive
+  pub fn new(temperature: Float, heat_capacity: Float, conductivity: Float) -> Self {
+    assert!(
+      heat_capacity > 0.0,
+      "The heat capacity must be positive, got {}",
+      heat_capacity
+    );
+    Self {
+      temperature,
+      heat_capacity,
+      conductivity,
+    }
+  }
+
+  pub fn get(&self) -> Float {
+    self.temperature
+  }
+
+  pub fn set(&mut self, temperature: Float) {
+    self.temperature = temperature;
+  }
+}
+
+impl Component for ParticleTemperature {
+  type Storage = VecStorage<Self>;
+}
+
+/// Phase change of a particle with `ParticleTemperature` and `ParticleDeformation`. Heat
+/// beyond the melting point is absorbed as latent heat instead of raising the temperature,
+/// and the absorbed portion gives the liquid fraction of the particle. Melting particles lose
+/// their shear modulus and gain the viscosity of the melt, so that fully melted particles flow
+/// like a viscous compressible liquid; they turn solid again when cooled.
+#[derive(Copy, Clone)]
+pub struct ParticleMelting {
+  pub melting_point: Float,
+
+  /// L, the specific latent heat of fusion
+  pub latent_heat: Float,
+
+  /// The latent heat absorbed so far, between 0 and L
+  pub latent: Float,
+
+  /// The dynamic viscosity of the melt, 0 for an inviscid melt
+  pub viscosity: Float,
+}
+
+impl ParticleMelting {
+  /// Panics if the latent heat is negative
+  pub fn new(melting_point: Float, latent_heat: Float) -> Self {
+    assert!(
+      latent_heat >= 0.0,
+      "The latent heat must not be negative, got {}",
+      latent_heat
+    );
+    Self {
+      melting_point,
+      latent_heat,
+      latent: 0.0,
+      viscosity: 0.0,
+    }
+  }
+
+  pub fn with_viscosity(mut self, viscosity: Float) -> Self {
+    self.viscosity = viscosity;
+    self
+  }
+
+  /// The viscous Cauchy stress of the melt given the velocity gradient and the liquid fraction
+  pub fn viscous_stress(&self, liquid_fraction: Float, grad_vp: &Matrix3f) -> Matrix3f {
+    liquid_fraction * self.viscosity * (grad_vp + grad_vp.transpose())
+  }
+
+  /// Exchange heat between the temperature and the latent heat buffer, returning the new
+  /// temperature. The specific heat capacity is `heat_capacity`
+  pub fn exchange(&mut self, temperature: Float, heat_capacity: Float) -> Float {
+    let excess = heat_capacity * (temperature - self.melting_point);
+    let absorbed = if excess > 0.0 {
+      excess.min(self.latent_heat - self.latent)
+    } else {
+      excess.max(-self.latent)
+    };
+    self.latent += absorbed;
+    temperature - absorbed / heat_capacity
+  }
+
+  /// The liquid fraction of the particle at the given temperature
+  pub fn liquid_fraction(&self, temperature: Float) -> Float {
+    if self.latent_heat > 0.0 {
+      (self.latent / self.latent_heat).clamp(0.0, 1.0)
+    } else if temperature > self.melting_point {
+      1.0
+    } else {
+      0.0
+    }
+  }
+}
+
+impl Component for ParticleMelting {
+  type Storage = VecStorage<Self>;
+}
@@ -0,0 +1,102 @@
+use specs::prelude::*;
+
+use crate::utils::*;
+
+/// A rigid body coupled with the grid. The body is represented by a set of samples of
+/// equal mass; each step they scatter the rigid velocity to the grid like particles, and
+/// the linear and angular momentum gathered back from the grid becomes the new velocity
+/// of the body. Materials and boundaries thus push the body and the other way round.
+#[derive(Clone, Debug)]
+pub struct RigidBody {
+  /// The total mass
+  pub mass: Float,
+
+  /// The inertia tensor in the body frame, around the center of mass
+  pub inertia: Matrix3f,
+
+  /// The center of mass in world space
+  pub position: Vector3f,
+
+  /// The rotation from the body frame to world space
+  pub orientation: UnitQuaternionf,
+
+  /// The linear velocity of the center of mass
+  pub velocity: Vector3f,
+
+  /// The angular velocity in world space
+  pub angular_velocity: Vector3f,
+
+  /// The samples relative to the center of mass in the body frame
+  pub samples: Vec<Vector3f>,
+}
+
+impl RigidBody {
+  /// Create a rigid body of the given mass from samples in world space, initially at rest
+  /// with the given orientation
+  ///
+  /// Panics if there are no samples
+  pub fn new(samples: &[Vector3f], mass: Float, orientation: UnitQuaternionf) -> Self {
+    assert!(!samples.is_empty(), "A rigid body needs at least one sample");
+    let position = samples.iter().fold(Vector3f::zeros(), |acc, s| acc + s) / samples.len() as Float;
+    let samples: Vec<Vector3f> = samples
+      .iter()
+      .map(|s| orientation.inverse_transform_vector(&(s - position)))
+      .collect();
+
+    // $I = \sum_s m_s (\|r_s\|^2 I - r_s r_s^T)$
+    let sample_mass = mass / samples.len() as Float;
+    let inertia = samples.iter().fold(Matrix3f::zeros(), |acc, r| {
+      acc + sample_mass * (Matrix3f::identity() * r.norm_squared() - r * r.transpose())
+    });
+
+    Self {
+      mass,
+      inertia,
+      position,
+      orientation,
+      velocity: Vector3f::zeros(),
+      angular_velocity: Vector3f::zeros(),
+      samples,
+    }
+  }
+
+  pub fn with_velocity(mut self, velocity: Vector3f) -> Self {
+    self.velocity = velocity;
+    self
+  }
+
+  pub fn with_angular_velocity(mut self, angular_velocity: Vector3f) -> Self {
+    self.angular_velocity = angular_velocity;
+    self
+  }
+
+  /// The mass of each sample
+  pub fn sample_mass(&self) -> Float {
+    self.mass / self.samples.len() as Float
+  }
+
+  /// The inertia tensor in world space $R I R^T$
+  pub fn world_inertia(&self) -> Matrix3f {
+    let r = self.orientation.to_rotation_matrix();
+    r * self.inertia * r.transpose()
+  }
+
+  /// The offsets of the samples from the center of mass in world space
+  pub fn sample_offsets<'a>(&'a self) -> impl Iterator<Item = Vector3f> + 'a {
+    self.samples.iter().map(move |r| self.orientation * r)
+  }
+
+  /// The velocity of the body at the given offset from its center of mass
+  pub fn velocity_at(&self, offset: &Vector3f) -> Vector3f {
+    self.velocity + self.angular_velocity.cross(offset)
+  }
+
+  /// The transformation from the body frame to world space
+  pub fn transform(&self) -> Isometry3f {
+    Isometry3f::from_parts(Translation3f::from(self.position), self.orientation)
+  }
+}
+
+impl Component for RigidBody {
+  type Storage = VecStorage<Self>;
+}
@@ -24,62 +24,138 @@ type SpecsWorld = specs::prelude::World;
 pub type Particle = specs::prelude::Entity;
 
 pub struct WorldBuilder<'a, 'b> {
+  grid_origin: Vector3f,
   grid_size: Vector3f,
-  grid_dx: f32,
-  particle_density: f32,
-  dt: f32,
+  planar: bool,
+  grid_dx: Float,
+  particle_density: Float,
+  dt: Float,
+  time_step: TimeStep,
+  transfer_scheme: TransferScheme,
+  flip_ratio: Float,
+  pipeline: Pipeline,
+  kernel: Box<dyn Kernel>,
+  shape_function: ShapeFunction,
+  contact_friction: Option<Float>,
+  integrator: Integrator,
   builder: DispatcherBuilder<'a, 'b>,
 }
 
 impl<'a, 'b> WorldBuilder<'a, 'b> {
   pub fn new() -> Self {
-    use specs::prelude::*;
-
-    // Then create basic builder
-    let mut builder = DispatcherBuilder::new();
-
-    // Put all systems into the world
-    builder.add(StepCounterSystem, "step_counter", &[]);
-    builder.add(CleanGridSystem, "clean_grid", &[]);
-    builder.add(P2GSystem, "p2g", &["clean_grid"]);
-    builder.add(GridM2VSystem, "grid_m2v", &["p2g"]);
-    builder.add(ApplyGravitySystem, "apply_gravity", &["grid_m2v"]);
-    builder.add(ApplyElasticitySystem, "apply_elasticity", &["apply_gravity"]);
-    builder.add(ApplyFrictionSystem, "apply_friction", &["apply_elasticity"]);
-    builder.add(GridF2VSystem, "grid_f2v", &["apply_gravity", "apply_elasticity"]);
-    builder.add(GridSetBoundarySystem, "grid_set_boundary", &["grid_f2v"]);
-    builder.add(EvolveDeformationSystem, "evolve_deformation", &["grid_set_boundary"]);
-    builder.add(G2PSystem, "g2p", &["grid_set_boundary"]);
-
     Self {
+      grid_origin: Vector3f::zeros(),
       grid_size: Vector3f::new(1.0, 1.0, 1.0),
+      planar: false,
       grid_dx: 0.02,
       particle_density: 2.0,
       dt: 0.001,
-      builder: builder,
+      time_step: TimeStep::Fixed,
+      transfer_scheme: TransferScheme::PicFlip,
+      flip_ratio: 0.95,
+      pipeline: Pipeline::Standard,
+      kernel: Box::new(QuadraticKernel),
+      shape_function: ShapeFunction::Kernel,
+      contact_friction: None,
+      integrator: Integrator::Explicit,
+      builder: DispatcherBuilder::new(),
     }
   }
 
   pub fn with_size(mut self, size: Vector3f) -> Self {
     self.grid_size = size;
+    self.planar = false;
+    self
+  }
+
+  /// Make the world 2D with the given size along x and y. Particles are then put with the
+  /// 2D regions, e.g. `World::put_circle`
+  pub fn with_size_2d(mut self, size: Vector2f) -> Self {
+    self.grid_size = Vector3f::new(size.x, size.y, 0.0);
+    self.planar = true;
     self
   }
 
-  pub fn with_dx(mut self, dx: f32) -> Self {
+  /// Set the world-space box covered by the grid, e.g. to center the world around the origin.
+  /// `with_size` keeps the current origin, which defaults to zero
+  pub fn with_bounds(mut self, bounds: BoundingBox) -> Self {
+    self.grid_origin = bounds.min.coords;
+    self.with_size(bounds.size())
+  }
+
+  /// Make the world 2D, covering the given box along x and y
+  pub fn with_bounds_2d(mut self, bounds: BoundingBox2D) -> Self {
+    self.grid_origin = Vector3f::new(bounds.min.x, bounds.min.y, 0.0);
+    self.with_size_2d(bounds.size())
+  }
+
+  pub fn with_dx(mut self, dx: Float) -> Self {
     self.grid_dx = dx;
     self
   }
 
-  pub fn with_density(mut self, density: f32) -> Self {
+  pub fn with_density(mut self, density: Float) -> Self {
     self.particle_density = density;
     self
   }
 
-  pub fn with_dt(mut self, dt: f32) -> Self {
+  pub fn with_dt(mut self, dt: Float) -> Self {
     self.dt = dt;
     self
   }
 
+  /// Set how the dt is chosen, e.g. `TimeStep::adaptive(min_dt, max_dt)` to derive it from
+  /// the particle velocities and material wave speeds every step instead of using `with_dt`
+  pub fn with_time_step(mut self, time_step: TimeStep) -> Self {
+    self.time_step = time_step;
+    self
+  }
+
+  pub fn with_transfer_scheme(mut self, scheme: TransferScheme) -> Self {
+    self.transfer_scheme = scheme;
+    self
+  }
+
+  /// Set the portion of FLIP velocity used by `TransferScheme::PicFlip`. Can be
+  /// overridden per particle with `ParticleFlipRatio`
+  pub fn with_flip_ratio(mut self, ratio: Float) -> Self {
+    self.flip_ratio = ratio;
+    self
+  }
+
+  pub fn with_pipeline(mut self, pipeline: Pipeline) -> Self {
+    self.pipeline = pipeline;
+    self
+  }
+
+  /// Set the interpolation kernel of the grid. Defaults to `QuadraticKernel`
+  pub fn with_kernel<K: Kernel + 'static>(mut self, kernel: K) -> Self {
+    self.kernel = Box::new(kernel);
+    self
+  }
+
+  /// Set how particle domains are taken into account in transfers, e.g. GIMP or
+  /// CPDI for scenes with large deformation. Defaults to `ShapeFunction::Kernel`
+  pub fn with_shape_function(mut self, shape_function: ShapeFunction) -> Self {
+    self.shape_function = shape_function;
+    self
+  }
+
+  /// Set the time integrator of the grid, e.g. `Integrator::implicit()` to take larger steps
+  /// with stiff materials. Only used by `Pipeline::Standard`
+  pub fn with_integrator(mut self, integrator: Integrator) -> Self {
+    self.integrator = integrator;
+    self
+  }
+
+  /// Give each body its own velocity field so that bodies slide on and separate from each
+  /// other instead of sticking together, with Coulomb friction `friction` between them.
+  /// Bodies are created by `put_region`
+  pub fn with_body_contact(mut self, friction: Float) -> Self {
+    self.contact_friction = Some(friction);
+    self
+  }
+
   pub fn with_system<T: for<'c> specs::RunNow<'c> + 'b>(mut self, system: T) -> Self {
     self.builder.add_thread_local(system);
     self
@@ -90,28 +166,113 @@ impl<'a, 'b> WorldBuilder<'a, 'b> {
     let x_dim = (self.grid_size.x / self.grid_dx) as usize;
     let y_dim = (self.grid_size.y / self.grid_dx) as usize;
     let z_dim = (self.grid_size.z / self.grid_dx) as usize;
-    let grid_dim = Vector3u::new(x_dim, y_dim, z_dim);
-    let grid = Grid::new(grid_dim, self.grid_dx);
+    let grid = if self.planar {
+      Grid::new_2d(Vector2u::new(x_dim, y_dim), self.grid_dx)
+    } else {
+      Grid::new(Vector3u::new(x_dim, y_dim, z_dim), self.grid_dx)
+    };
+    let mut grid = grid
+      .with_origin(self.grid_origin)
+      .with_kernel(self.kernel)
+      .with_shape_function(self.shape_function);
+    if let Some(friction) = self.contact_friction {
+      grid = grid.with_body_contact(friction);
+    }
+
+    // Put all systems of the pipeline into the dispatcher
+    let mut builder = self.builder;
+    match self.pipeline {
+      Pipeline::Standard => Self::add_standard_systems(&mut builder),
+      Pipeline::MlsMpm => Self::add_mls_mpm_systems(&mut builder),
+    }
+
+    // MLS-MPM relies on the affine velocity
+    let transfer_scheme = match self.pipeline {
+      Pipeline::Standard => self.transfer_scheme,
+      Pipeline::MlsMpm => TransferScheme::Apic,
+    };
 
     // Then generate the world & dispatcher
     use specs::prelude::WorldExt;
     let mut world = specs::prelude::World::new();
-    let mut dispatcher = self.builder.build();
+    let mut dispatcher = builder.build();
     dispatcher.setup(&mut world);
 
     // Set the world's grid to be grid
     *world.fetch_mut::<Grid>() = grid;
 
-    // Set the world's Delta Time
+    // Set the world's Delta Time and how it evolves
     world.fetch_mut::<DeltaTime>().set(self.dt);
+    *world.fetch_mut::<TimeStep>() = self.time_step;
+
+    // Set the world's transfer scheme and PIC/FLIP blending
+    *world.fetch_mut::<TransferScheme>() = transfer_scheme;
+    world.fetch_mut::<FlipRatio>().set(self.flip_ratio);
+
+    // Set the world's pipeline and integrator. The integrator is inserted since no system of
+    // the MLS-MPM pipeline reads it
+    *world.fetch_mut::<Pipeline>() = self.pipeline;
+    world.insert(self.integrator);
 
     // Return the world
     World {
       dispatcher,
       world,
       particle_density: self.particle_density,
+      num_bodies: 0,
     }
   }
+
+  fn add_standard_systems(builder: &mut DispatcherBuilder<'a, 'b>) {
+    builder.add(StepCounterSystem, "step_counter", &[]);
+    builder.add(AdaptDeltaTimeSystem, "adapt_delta_time", &[]);
+    builder.add(AdvanceTimeSystem, "advance_time", &["adapt_delta_time"]);
+    builder.add(CleanGridSystem, "clean_grid", &[]);
+    builder.add(P2GSystem, "p2g", &["clean_grid"]);
+    builder.add(RigidBodyP2GSystem, "rigid_body_p2g", &["p2g"]);
+    builder.add(P2GHeatSystem, "p2g_heat", &["p2g"]);
+    builder.add(GridHeatDiffusionSystem, "grid_heat_diffusion", &["p2g_heat"]);
+    builder.add(GridM2VSystem, "grid_m2v", &["rigid_body_p2g"]);
+    builder.add(ApplyGravitySystem, "apply_gravity", &["grid_m2v"]);
+    builder.add(ApplyElasticitySystem, "apply_elasticity", &["apply_gravity"]);
+    builder.add(ApplyFluidStressSystem, "apply_fluid_stress", &["apply_elasticity"]);
+    builder.add(ApplyFrictionSystem, "apply_friction", &["apply_fluid_stress"]);
+    builder.add(GridF2VSystem, "grid_f2v", &["apply_friction"]);
+    builder.add(ImplicitGridSolveSystem, "implicit_grid_solve", &["grid_f2v"]);
+    builder.add(GridContactSystem, "grid_contact", &["implicit_grid_solve"]);
+    builder.add(GridSetBoundarySystem, "grid_set_boundary", &["grid_contact"]);
+    builder.add(EvolveDeformationSystem, "evolve_deformation", &["grid_set_boundary"]);
+    builder.add(EvolveDamageSystem, "evolve_damage", &["evolve_deformation"]);
+    builder.add(EvolveFluidSystem, "evolve_fluid", &["grid_set_boundary"]);
+    builder.add(G2PSystem, "g2p", &["grid_set_boundary"]);
+    builder.add(RigidBodyG2PSystem, "rigid_body_g2p", &["grid_set_boundary"]);
+    builder.add(G2PHeatSystem, "g2p_heat", &["grid_heat_diffusion"]);
+    builder.add(EvolvePhaseSystem, "evolve_phase", &["g2p_heat", "evolve_damage"]);
+  }
+
+  fn add_mls_mpm_systems(builder: &mut DispatcherBuilder<'a, 'b>) {
+    builder.add(StepCounterSystem, "step_counter", &[]);
+    builder.add(AdaptDeltaTimeSystem, "adapt_delta_time", &[]);
+    builder.add(AdvanceTimeSystem, "advance_time", &["adapt_delta_time"]);
+    builder.add(CleanGridSystem, "clean_grid", &[]);
+    builder.add(MlsP2GSystem, "p2g", &["clean_grid"]);
+    builder.add(RigidBodyP2GSystem, "rigid_body_p2g", &["p2g"]);
+    builder.add(P2GHeatSystem, "p2g_heat", &["p2g"]);
+    builder.add(GridHeatDiffusionSystem, "grid_heat_diffusion", &["p2g_heat"]);
+    builder.add(GridM2VSystem, "grid_m2v", &["rigid_body_p2g"]);
+    builder.add(ApplyGravitySystem, "apply_gravity", &["grid_m2v"]);
+    builder.add(ApplyFrictionSystem, "apply_friction", &["apply_gravity"]);
+    builder.add(GridF2VSystem, "grid_f2v", &["apply_gravity", "apply_friction"]);
+    builder.add(GridContactSystem, "grid_contact", &["grid_f2v"]);
+    builder.add(GridSetBoundarySystem, "grid_set_boundary", &["grid_contact"]);
+    builder.add(G2PSystem, "g2p", &["grid_set_boundary"]);
+    builder.add(RigidBodyG2PSystem, "rigid_body_g2p", &["grid_set_boundary"]);
+    builder.add(EvolveDeformationSystem, "evolve_deformation", &["g2p"]);
+    builder.add(EvolveDamageSystem, "evolve_damage", &["evolve_deformation"]);
+    builder.add(EvolveFluidSystem, "evolve_fluid", &["g2p"]);
+    builder.add(G2PHeatSystem, "g2p_heat", &["grid_heat_diffusion"]);
+    builder.add(EvolvePhaseSystem, "evolve_phase", &["g2p_heat", "evolve_damage"]);
+  }
 }
 
 pub struct ParticlesHandle<'w, 'a, 'b> {
@@ -124,14 +285,18 @@ impl<'w, 'a, 'b> ParticlesHandle<'w, 'a, 'b> {
     self.entities[0]
   }
 
-  pub fn with<T: specs::prelude::Component + Clone + Send + Sync>(self, c: T) -> Self {
+  pub fn with<T>(self, c: T) -> Self
+  where
+    T: specs::prelude::Component + Clone + Send + Sync,
+    T::Storage: Default,
+  {
     for &ent in &self.entities {
       self.world.insert(ent, c.clone());
     }
     self
   }
 
-  pub fn hide_random_portion(self, percentage: f32) -> Self {
+  pub fn hide_random_portion(self, percentage: Float) -> Self {
     for &ent in &self.entities {
       if random() > percentage {
         self.world.remove::<Hidden>(ent);
@@ -152,7 +317,8 @@ impl<'w, 'a, 'b> ParticlesHandle<'w, 'a, 'b> {
 
 pub struct World<'a, 'b> {
   pub world: SpecsWorld,
-  pub particle_density: f32,
+  pub particle_density: Float,
+  num_bodies: usize,
   dispatcher: specs::Dispatcher<'a, 'b>,
 }
 
@@ -162,13 +328,43 @@ impl<'a, 'b> World<'a, 'b> {
     self.dispatcher.dispatch(&mut self.world);
   }
 
-  /// Set the dt of the world
-  pub fn set_dt(&mut self, dt: f32) {
+  /// Advance the world by a frame of `frame_dt`, taking as many steps as needed. The last
+  /// steps are shortened to land exactly on the end of the frame. Returns the number of steps.
+  /// Panics if `frame_dt` is not positive
+  pub fn advance_frame(&mut self, frame_dt: Float) -> usize {
+    assert!(frame_dt > 0.0, "Frame of {} is not positive", frame_dt);
+    let dt = self.dt();
+    self.world.fetch_mut::<SimulationTime>().begin_frame(frame_dt);
+    let mut num_steps = 0;
+    while self.world.fetch::<SimulationTime>().in_frame() {
+      self.step();
+      num_steps += 1;
+    }
+
+    // A fixed dt is only shortened within the frame
+    if !self.world.fetch::<TimeStep>().is_adaptive() {
+      self.set_dt(dt);
+    }
+    num_steps
+  }
+
+  /// Get the simulated time so far
+  pub fn time(&self) -> Float {
+    self.world.fetch::<SimulationTime>().get()
+  }
+
+  /// Set the dt of the world. With an adaptive `TimeStep` it is overridden by the next step
+  pub fn set_dt(&mut self, dt: Float) {
     self.world.fetch_mut::<DeltaTime>().set(dt);
   }
 
+  /// Get the dt of the world, i.e. the one taken by the last step when the `TimeStep` is adaptive
+  pub fn dt(&self) -> Float {
+    self.world.fetch::<DeltaTime>().get()
+  }
+
   /// Add `Hidden` marker to a random portion of all the present particles
-  pub fn hide_random_portion(&mut self, percentage: f32) {
+  pub fn hide_random_portion(&mut self, percentage: Float) {
     use specs::prelude::*;
     let (entities, poses): (Entities, ReadStorage<ParticlePosition>) = self.world.system_data();
     let mut hiddens: WriteStorage<Hidden> = self.world.system_data();
@@ -199,19 +395,25 @@ impl<'a, 'b> World<'a, 'b> {
     store.get(p).map(T::clone)
   }
 
-  /// Insert (will override if already presented) a component to a given particle
-  pub fn insert<T: specs::prelude::Component + Send + Sync>(&mut self, p: Particle, c: T) {
+  /// Insert (will override if already presented) a component to a given particle. The
+  /// component type will be registered to the world if it is not yet registered
+  pub fn insert<T>(&mut self, p: Particle, c: T)
+  where
+    T: specs::prelude::Component + Send + Sync,
+    T::Storage: Default,
+  {
     use specs::prelude::*;
-    if let Some(mut store) = self.world.try_fetch_mut::<WriteStorage<T>>() {
-      store.insert(p, c).unwrap();
+    if !self.world.has_value::<specs::storage::MaskedStorage<T>>() {
+      self.world.register::<T>();
     }
+    self.world.write_storage::<T>().insert(p, c).unwrap();
   }
 
   /// Remove a component of a given particle
   pub fn remove<T: specs::prelude::Component + Send + Sync>(&mut self, p: Particle) {
     use specs::prelude::*;
-    if let Some(mut store) = self.world.try_fetch_mut::<WriteStorage<T>>() {
-      store.remove(p);
+    if self.world.has_value::<specs::storage::MaskedStorage<T>>() {
+      self.world.write_storage::<T>().remove(p);
     }
   }
 
@@ -222,7 +424,7 @@ impl<'a, 'b> World<'a, 'b> {
   }
 
   /// Get the dx, the distance between a pair of neighbor node, of the grid
-  pub fn dx(&self) -> f32 {
+  pub fn dx(&self) -> Float {
     let grid = self.world.fetch::<Grid>();
     grid.dx
   }
@@ -233,14 +435,27 @@ impl<'a, 'b> World<'a, 'b> {
     grid.size()
   }
 
+  /// Get the world-space box covered by the grid
+  pub fn bounds(&self) -> BoundingBox {
+    let grid = self.world.fetch::<Grid>();
+    grid.bounds()
+  }
+
+  /// Whether the world is 2D, i.e. built with `WorldBuilder::with_size_2d`
+  pub fn is_2d(&self) -> bool {
+    let grid = self.world.fetch::<Grid>();
+    grid.planar
+  }
+
   /// Put a boundary. Accept a callback function where given a node index, return an optional
   /// boundary. If `None` is returned from the callback, then nothing will be done; If `Some`
-  /// is returned, then the boundary at that location will be updated
+  /// is returned, then the boundary at that location will be updated. The callback is called
+  /// on every node of the grid, active or not, without activating any block
   pub fn put_boundary<F: Fn(Vector3u) -> Option<Boundary>>(&mut self, f: F) {
     let mut grid = self.world.fetch_mut::<Grid>();
     for node_index in grid.indices() {
       if let Some(b) = f(node_index) {
-        grid.get_node_mut(node_index).boundary = b;
+        grid.set_boundary(node_index, b);
       }
     }
   }
@@ -249,54 +464,93 @@ impl<'a, 'b> World<'a, 'b> {
   /// function `f`, which should accept a type of `Wall` and return a corresponding boundary.
   ///
   /// As a difference to `put_boundary`, no `None` would be accepted here.
-  pub fn put_wrapping_boundary<F: Fn(Wall) -> Boundary>(&mut self, thickness: f32, f: F) {
-    let dim = self.dimension();
+  pub fn put_wrapping_boundary<F: Fn(Wall) -> Boundary>(&mut self, thickness: Float, f: F) {
+    let (dim, planar) = (self.dimension(), self.is_2d());
     let num_nodes = (thickness / self.dx()) as usize;
-    self.put_boundary(|node_index| {
-      if node_index.x < num_nodes {
-        Some(f(Wall::Left))
-      } else if node_index.x > dim.x - num_nodes {
-        Some(f(Wall::Right))
-      } else if node_index.y < num_nodes {
-        Some(f(Wall::Bottom))
-      } else if node_index.y > dim.y - num_nodes {
-        Some(f(Wall::Up))
-      } else if node_index.z < num_nodes {
-        Some(f(Wall::Back))
-      } else if node_index.z > dim.z - num_nodes {
-        Some(f(Wall::Front))
-      } else {
-        None
+    self.put_boundary(|node_index| Self::wall_at(dim, planar, num_nodes, node_index).map(&f))
+  }
+
+  /// The wall that a node index is in, given the number of nodes of wall thickness. A 2D
+  /// world has no back and front walls
+  fn wall_at(dim: Vector3u, planar: bool, num_nodes: usize, node_index: Vector3u) -> Option<Wall> {
+    if node_index.x < num_nodes {
+      Some(Wall::Left)
+    } else if node_index.x > dim.x - num_nodes {
+      Some(Wall::Right)
+    } else if node_index.y < num_nodes {
+      Some(Wall::Bottom)
+    } else if node_index.y > dim.y - num_nodes {
+      Some(Wall::Up)
+    } else if planar {
+      None
+    } else if node_index.z < num_nodes {
+      Some(Wall::Back)
+    } else if node_index.z > dim.z - num_nodes {
+      Some(Wall::Front)
+    } else {
+      None
+    }
+  }
+
+  /// Put a fixed temperature to nodes. Accept a callback function where given a node index,
+  /// return an optional temperature. Same as `put_boundary`, nodes are only updated when
+  /// `Some` is returned
+  pub fn put_temperature_boundary<F: Fn(Vector3u) -> Option<Float>>(&mut self, f: F) {
+    let mut grid = self.world.fetch_mut::<Grid>();
+    for node_index in grid.indices() {
+      if let Some(t) = f(node_index) {
+        grid.set_boundary_temperature(node_index, Some(t));
       }
-    })
+    }
+  }
+
+  /// Put fixed temperatures to the walls of the world within a given thickness. The callback
+  /// function `f` gives the temperature of each `Wall`, or `None` for an insulating wall.
+  ///
+  /// ## Example
+  ///
+  /// ``` rust
+  /// # use mpm_rs::*;
+  /// # let mut world = WorldBuilder::new().build();
+  /// // Heat the floor while keeping the other walls insulating
+  /// world.put_wrapping_temperature_boundary(0.04, |wall| match wall {
+  ///   Wall::Bottom => Some(100.0),
+  ///   _ => None,
+  /// });
+  /// ```
+  pub fn put_wrapping_temperature_boundary<F: Fn(Wall) -> Option<Float>>(&mut self, thickness: Float, f: F) {
+    let (dim, planar) = (self.dimension(), self.is_2d());
+    let num_nodes = (thickness / self.dx()) as usize;
+    self.put_temperature_boundary(|node_index| Self::wall_at(dim, planar, num_nodes, node_index).and_then(&f))
   }
 
   /// Put the `SetZero` boundary type to the boundary of the world within a given thickness
-  pub fn put_sticky_boundary(&mut self, thickness: f32) {
+  pub fn put_sticky_boundary(&mut self, thickness: Float) {
     self.put_wrapping_boundary(thickness, |_| Boundary::Sticky)
   }
 
   /// Put the `Sliding` boundary type to the boundary of the world within a given thickness.
   /// The normal of the boundary will be automatically the normal of the box pointing inward.
-  pub fn put_sliding_boundary(&mut self, thickness: f32) {
+  pub fn put_sliding_boundary(&mut self, thickness: Float) {
     self.put_wrapping_boundary(thickness, |w| Boundary::Sliding { normal: w.normal() })
   }
 
   /// Put the `Friction` boundary type to the boundary of the world within a given thickness.
   /// The normal of the boundary will be automatically the normal of the box pointing inward.
   /// The friction constant is given by the argument `mu`.
-  pub fn put_friction_boundary(&mut self, thickness: f32, mu: f32) {
+  pub fn put_friction_boundary(&mut self, thickness: Float, mu: Float) {
     self.put_wrapping_boundary(thickness, |w| Boundary::Friction { normal: w.normal(), mu })
   }
 
   /// Put a single particle at a given position with a given mass.
-  pub fn put_particle<'w>(&'w mut self, pos: Vector3f, mass: f32) -> ParticlesHandle<'w, 'a, 'b> {
+  pub fn put_particle<'w>(&'w mut self, pos: Vector3f, mass: Float) -> ParticlesHandle<'w, 'a, 'b> {
     use specs::prelude::*;
     let ent = self
       .world
       .create_entity()
       .with(ParticlePosition(pos))
       .with(ParticleVelocity(Vector3f::zeros()))
+      .with(ParticleAffineVelocity::zeros())
       .with(ParticleMass(mass))
       .build();
     ParticlesHandle {
@@ -306,55 +560,103 @@ impl<'a, 'b> World<'a, 'b> {
   }
 
   /// Put a given region into the world with a transformation and a mass. The particles will be
-  /// poisson sampled.
-  pub fn put_region<'w, R>(&'w mut self, reg: R, transf: Similarity3f, mass: f32) -> ParticlesHandle<'w, 'a, 'b>
+  /// poisson sampled, and all belong to a new `ParticleBody`.
+  pub fn put_region<'w, R>(&'w mut self, reg: R, transf: Similarity3f, mass: Float) -> ParticlesHandle<'w, 'a, 'b>
   where
     R: Region,
   {
-    let mut entities = vec![];
-
-    // Cache important data
     let radius = self.dx() / self.particle_density;
-    let inv_transf = transf.inverse();
+    let samples = self.sample_region(&reg, &transf);
+    self.put_samples(samples, radius.powi(3), mass)
+  }
 
-    // Get the bound transformed to the final position that the
-    // object is going to be at
-    let bb = reg.bound().transform(&transf);
+  /// Put a given 2D region into a 2D world with a transformation and a mass. Like `put_region`,
+  /// the particles are poisson sampled and belong to a new `ParticleBody`. They lie in the
+  /// plane of the grid, and each of them spans one cell along z
+  pub fn put_region_2d<'w, R>(&'w mut self, reg: R, transf: Similarity2f, mass: Float) -> ParticlesHandle<'w, 'a, 'b>
+  where
+    R: Region2D,
+  {
+    let (dx, z) = (self.dx(), self.world.fetch::<Grid>().plane_z());
+    let radius = dx / self.particle_density;
+    let inv_transf = transf.inverse();
+    let samples = reg
+      .bound()
+      .transform(&transf)
+      .gen_poisson_samples(radius)
+      .filter(|sample| reg.contains(&(inv_transf * Point2f::from(*sample))))
+      .map(|sample| Vector3f::new(sample.x, sample.y, z))
+      .collect();
+    self.put_samples(samples, radius.powi(2) * dx, mass)
+  }
 
-    // Use that bounding box to generate poisson samples. The `sample`
-    // here is at the world space
-    for sample in bb.gen_poisson_samples(radius) {
-      // Use inverse transform to get the sample in object local space
-      let reg_ppos = inv_transf * Math::point_of_vector(&sample);
-
-      // Check if the region contains the local space point, if is, then
-      // add the world space position to the world
-      if reg.contains(&reg_ppos) {
-        let hdl = self.put_particle(sample, 0.0).with(ParticleVolume::new(radius.powi(3)));
-        entities.push(hdl.first());
-      }
+  /// Put particles of a given volume at the samples, sharing the mass as a new body
+  fn put_samples<'w>(&'w mut self, samples: Vec<Vector3f>, volume: Float, mass: Float) -> ParticlesHandle<'w, 'a, 'b> {
+    let mut entities = vec![];
+    for sample in samples {
+      let hdl = self.put_particle(sample, 0.0).with(ParticleVolume::new(volume));
+      entities.push(hdl.first());
     }
 
     // Finally calculate the mass being distributed to each particle
-    let num_particles = entities.len() as f32;
+    let num_particles = entities.len() as Float;
     let ind_mass = mass / num_particles;
+    let body = ParticleBody::new(self.num_bodies);
+    self.num_bodies += 1;
     for &ent in &entities {
       self.insert(ent, ParticleMass::new(ind_mass));
+      self.insert(ent, body);
     }
 
     // Return the handle
     ParticlesHandle { world: self, entities }
   }
 
+  /// Put a rigid body of the given region, transformation and mass into the world. Like
+  /// `put_region`, the rigid body gets a new `ParticleBody`. Returns the entity holding
+  /// the `RigidBody` component.
+  ///
+  /// Panics if the region is too small to hold any sample
+  pub fn put_rigid_body<R: Region>(&mut self, reg: R, transf: Similarity3f, mass: Float) -> specs::prelude::Entity {
+    use specs::prelude::*;
+    let samples = self.sample_region(&reg, &transf);
+    let body = ParticleBody::new(self.num_bodies);
+    self.num_bodies += 1;
+    self
+      .world
+      .create_entity()
+      .with(RigidBody::new(&samples, mass, transf.isometry.rotation))
+      .with(body)
+      .build()
+  }
+
+  /// Poisson sample a region with a transformation. The samples are in world space
+  fn sample_region<R: Region>(&self, reg: &R, transf: &Similarity3f) -> Vec<Vector3f> {
+    // Cache important data
+    let radius = self.dx() / self.particle_density;
+    let inv_transf = transf.inverse();
+
+    // Get the bound transformed to the final position that the
+    // object is going to be at
+    let bb = reg.bound().transform(transf);
+
+    // Use that bounding box to generate poisson samples. The `sample`
+    // here is at the world space. Use inverse transform to get the sample
+    // in object local space, and keep it if the region contains it
+    bb.gen_poisson_samples(radius)
+      .filter(|sample| reg.contains(&(inv_transf * Math::point_of_vector(sample))))
+      .collect()
+  }
+
   /// A shortcut function adding a ball to the world
-  pub fn put_ball<'w>(&'w mut self, center: Vector3f, radius: f32, mass: f32) -> ParticlesHandle<'w, 'a, 'b> {
+  pub fn put_ball<'w>(&'w mut self, center: Vector3f, radius: Float, mass: Float) -> ParticlesHandle<'w, 'a, 'b> {
     let reg = Sphere::new(radius);
     let translation = Translation3f::from(center);
     self.put_region(reg, na::convert(translation), mass)
   }
 
   /// A shortcut function adding a axis-aligned cube into the world
-  pub fn put_cube<'w>(&'w mut self, min: Vector3f, max: Vector3f, mass: f32) -> ParticlesHandle<'w, 'a, 'b> {
+  pub fn put_cube<'w>(&'w mut self, min: Vector3f, max: Vector3f, mass: Float) -> ParticlesHandle<'w, 'a, 'b> {
     let size = max - min;
     let pos = min + size / 2.0;
     let reg = Cube::new(size);
@@ -362,12 +664,34 @@ impl<'a, 'b> World<'a, 'b> {
     self.put_region(reg, na::convert(translation), mass)
   }
 
+  /// A shortcut function adding a circle to a 2D world
+  pub fn put_circle<'w>(&'w mut self, center: Vector2f, radius: Float, mass: Float) -> ParticlesHandle<'w, 'a, 'b> {
+    let reg = Circle::new(radius);
+    let translation = Translation2f::from(center);
+    self.put_region_2d(reg, na::convert(translation), mass)
+  }
+
+  /// A shortcut function adding an axis-aligned rectangle to a 2D world
+  pub fn put_rectangle<'w>(&'w mut self, min: Vector2f, max: Vector2f, mass: Float) -> ParticlesHandle<'w, 'a, 'b> {
+    let size = max - min;
+    let pos = min + size / 2.0;
+    let reg = Rectangle::new(size);
+    let translation = Translation2f::from(pos);
+    self.put_region_2d(reg, na::convert(translation), mass)
+  }
+
+  /// A shortcut function adding a polygon, given by its vertices in order, to a 2D world
+  pub fn put_polygon<'w>(&'w mut self, vertices: &[Vector2f], mass: Float) -> ParticlesHandle<'w, 'a, 'b> {
+    let reg = Polygon::new(vertices.iter().map(|v| Point2f::from(*v)).collect());
+    self.put_region_2d(reg, Similarity2f::identity(), mass)
+  }
+
   /// A shortcut function adding a tetrahedron mesh into the world
   pub fn put_tetra_mesh<'w>(
     &'w mut self,
     mesh: &TetrahedronMesh,
     transf: Similarity3f,
-    mass: f32,
+    mass: Float,
   ) -> ParticlesHandle<'w, 'a, 'b> {
     let reg = TetMesh::new(mesh);
     self.put_region(reg, transf, mass)
@@ -1,11 +1,13 @@
-pub struct DeltaTime(f32);
+use crate::utils::*;
+
+pub struct DeltaTime(Float);
 
 impl DeltaTime {
-  pub fn get(&self) -> f32 {
+  pub fn get(&self) -> Float {
     self.0
   }
 
-  pub fn set(&mut self, dt: f32) {
+  pub fn set(&mut self, dt: Float) {
     self.0 = dt;
   }
 }
@@ -0,0 +1,21 @@
+use crate::utils::*;
+
+/// The portion of FLIP velocity when blending PIC and FLIP in G2P. `0.0` gives
+/// pure PIC (dissipative) while `1.0` gives pure FLIP (noisy).
+pub struct FlipRatio(Float);
+
+impl FlipRatio {
+  pub fn get(&self) -> Float {
+    self.0
+  }
+
+  pub fn set(&mut self, ratio: Float) {
+    self.0 = ratio;
+  }
+}
+
+impl Default for FlipRatio {
+  fn default() -> Self {
+    Self(0.95)
+  }
+}
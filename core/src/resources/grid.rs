@@ -1,3 +1,5 @@
+use rayon::prelude::*;
+
 use crate::utils::*;
 
 /// The boundary type information associated with each Node
@@ -16,19 +18,69 @@ pub enum Boundary {
 
   Friction {
     normal: Vector3f,
-    mu: f32,
+    mu: Float,
   },
 }
 
+impl Boundary {
+  /// The velocity after applying the boundary
+  pub fn project(&self, velocity: Vector3f) -> Vector3f {
+    match self {
+      Boundary::None => velocity,
+      Boundary::Sticky => Vector3f::zeros(),
+      Boundary::Sliding { normal } | Boundary::Friction { normal, .. } => {
+        velocity - Float::min(Vector3f::dot(&velocity, normal), 0.0) * normal
+      }
+    }
+  }
+}
+
+/// The velocity field of a single body at a node, used to resolve contact between bodies
+#[derive(Copy, Clone, Debug)]
+pub struct BodyField {
+  /// The body this field belongs to
+  pub body: usize,
+
+  /// The mass of the body at the node
+  pub mass: Float,
+
+  /// The velocity of the body before and after the grid update
+  pub velocity_temp: Vector3f,
+  pub velocity: Vector3f,
+
+  /// The momentum of the body at the node
+  pub momentum: Vector3f,
+
+  /// The force acting on the body at the node
+  pub force: Vector3f,
+
+  /// The gradient of the body's mass $\sum_p m_p \nabla w_{ip}$, pointing into the body
+  pub mass_gradient: Vector3f,
+}
+
+impl BodyField {
+  fn new(body: usize) -> Self {
+    Self {
+      body,
+      mass: 0.0,
+      velocity_temp: Vector3f::zeros(),
+      velocity: Vector3f::zeros(),
+      momentum: Vector3f::zeros(),
+      force: Vector3f::zeros(),
+      mass_gradient: Vector3f::zeros(),
+    }
+  }
+}
+
 /// The Node of the Grid
 ///
-/// The `index` and `boundary` information of Node will be kept immutable, while
-/// all the other information should be cleaned to `0` at the beginning of each step.
-/// When initializing, we only need `index` and `boundary` information.
-#[derive(Copy, Clone, Debug)]
+/// Nodes only live within the active blocks of the grid. The `boundary` and
+/// `boundary_temperature` are copied from the grid when the block is activated, while
+/// all the other information starts at `0` in each step.
+#[derive(Clone, Debug)]
 pub struct Node {
   /// The mass of the node
-  pub mass: f32,
+  pub mass: Float,
 
   /// The lagrangian velocity at the node
   pub velocity_temp: Vector3f,
@@ -43,6 +95,24 @@ pub struct Node {
   /// The type of boundary. Used to describe the boundary behavior of this node.
   /// should be default to `Boundary::None`
   pub boundary: Boundary,
+
+  /// The heat capacity $\sum_p w_{ip} m_p c_p$ at the node
+  pub heat_capacity: Float,
+
+  /// The temperature at the node before and after heat diffusion
+  pub temperature_temp: Float,
+  pub temperature: Float,
+
+  /// The thermal conductivity at the node
+  pub conductivity: Float,
+
+  /// The fixed temperature of the node, if any. Like `boundary`, it is kept through steps
+  pub boundary_temperature: Option<Float>,
+
+  /// The velocity fields of the bodies around the node. Only filled when the grid has
+  /// body contact enabled; otherwise it stays an empty `Vec`, which does not allocate and
+  /// only costs its header in each node. Owning the fields is why `Node` is not `Copy`
+  pub fields: Vec<BodyField>,
 }
 
 impl Node {
@@ -55,35 +125,75 @@ impl Node {
       momentum: Vector3f::zeros(),
       force: Vector3f::zeros(),
       boundary: Boundary::None,
+      heat_capacity: 0.0,
+      temperature_temp: 0.0,
+      temperature: 0.0,
+      conductivity: 0.0,
+      boundary_temperature: None,
+      fields: Vec::new(),
+    }
+  }
+
+  /// Get the velocity field of a body, if the body is present at the node
+  pub fn field(&self, body: usize) -> Option<&BodyField> {
+    self.fields.iter().find(|field| field.body == body)
+  }
+
+  /// Get the velocity field of a body, creating it if the body is not yet present at the node
+  pub fn field_mut(&mut self, body: usize) -> &mut BodyField {
+    match self.fields.iter().position(|field| field.body == body) {
+      Some(i) => &mut self.fields[i],
+      None => {
+        self.fields.push(BodyField::new(body));
+        self.fields.last_mut().unwrap()
+      }
+    }
+  }
+
+  /// Add a force to the node, and to the velocity field of `body` if any
+  pub fn add_force(&mut self, body: Option<usize>, force: Vector3f) {
+    self.force += force;
+    if let Some(body) = body {
+      self.field_mut(body).force += force;
+    }
+  }
+
+  /// The velocities before and after the grid update seen by a particle of `body`. Falls
+  /// back to the shared velocity of the node when the body has no field here
+  pub fn velocities(&self, body: Option<usize>) -> (Vector3f, Vector3f) {
+    match body.and_then(|body| self.field(body)) {
+      Some(field) => (field.velocity_temp, field.velocity),
+      None => (self.velocity_temp, self.velocity),
     }
   }
 }
 
-/// The weight iterator type storing essential information traversing
-/// the neighboring nodes around a point
-pub struct WeightIterator {
-  dx: f32,
+/// Weights of the nodes around a point given by a kernel, as the tensor
+/// product of the 1D weights along each axis
+struct TensorWeightIterator {
+  dx: Float,
   dim: Vector3u,
+  support: usize,
   base_node: Vector3i,
   curr_node: Vector3i,
-  wx: Vector3f,
-  wy: Vector3f,
-  wz: Vector3f,
-  dwx: Vector3f,
-  dwy: Vector3f,
-  dwz: Vector3f,
+  wx: Stencil,
+  wy: Stencil,
+  wz: Stencil,
+  dwx: Stencil,
+  dwy: Stencil,
+  dwz: Stencil,
 }
 
-impl Iterator for WeightIterator {
-  /// (Node Index, Weight, Weight Gradient)
-  type Item = (Vector3u, f32, Vector3f);
+impl Iterator for TensorWeightIterator {
+  type Item = (Vector3u, Float, Vector3f);
 
   fn next(&mut self) -> Option<Self::Item> {
     loop {
       let i = self.curr_node.x as usize;
       let j = self.curr_node.y as usize;
       let k = self.curr_node.z as usize;
-      if i < 3 && j < 3 && k < 3 {
+      let n = self.support;
+      if i < n && j < n && k < n {
         // Get node index
         let node_index = self.base_node + self.curr_node;
 
@@ -107,8 +217,8 @@ impl Iterator for WeightIterator {
         let grad_w = Vector3f::new(dwijk_dx, dwijk_dy, dwijk_dz);
 
         // Compute the `curr_node` for next step
-        if self.curr_node.x == 2 {
-          if self.curr_node.y == 2 {
+        if self.curr_node.x as usize == n - 1 {
+          if self.curr_node.y as usize == n - 1 {
             self.curr_node.z += 1;
             self.curr_node.x = 0;
             self.curr_node.y = 0;
@@ -136,6 +246,53 @@ impl Iterator for WeightIterator {
   }
 }
 
+enum Weights {
+  Tensor(TensorWeightIterator),
+  List(std::vec::IntoIter<(Vector3u, Float, Vector3f)>),
+}
+
+/// The weight iterator type storing essential information traversing
+/// the neighboring nodes around a point
+pub struct WeightIterator {
+  weights: Weights,
+}
+
+impl Iterator for WeightIterator {
+  /// (Node Index, Weight, Weight Gradient)
+  type Item = (Vector3u, Float, Vector3f);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    match &mut self.weights {
+      Weights::Tensor(it) => it.next(),
+      Weights::List(it) => it.next(),
+    }
+  }
+}
+
+impl WeightIterator {
+  /// The smallest and largest node indices of the weights along each axis, `None` if there are
+  /// no weights
+  fn bounds(&self) -> Option<(Vector3u, Vector3u)> {
+    match &self.weights {
+      Weights::Tensor(it) => {
+        let min = it.base_node.map(|i| i.max(0));
+        let max = it
+          .base_node
+          .zip_map(&it.dim, |i, d| (i + it.support as i32 - 1).min(d as i32 - 1));
+        if min.x <= max.x && min.y <= max.y && min.z <= max.z {
+          Some((min.map(|i| i as usize), max.map(|i| i as usize)))
+        } else {
+          None
+        }
+      }
+      Weights::List(it) => it.as_slice().iter().fold(None, |bounds, (node_index, _, _)| {
+        let (min, max) = bounds.unwrap_or((*node_index, *node_index));
+        Some((min.zip_map(node_index, usize::min), max.zip_map(node_index, usize::max)))
+      }),
+    }
+  }
+}
+
 /// Node index iterator iterate through all the indices of a grid
 pub struct NodeIndexIterator {
   dim: Vector3u,
@@ -170,17 +327,81 @@ impl Iterator for NodeIndexIterator {
   }
 }
 
+/// A particle adding values to its neighbor nodes with `Grid::scatter`
+pub struct ScatterParticle<T> {
+  /// The position, volume and deformation gradient giving the weights of the particle, as in
+  /// `Grid::particle_weights`
+  pub position: Vector3f,
+  pub volume: Float,
+  pub f: Matrix3f,
+
+  /// The values of the particle to add to the nodes
+  pub data: T,
+}
+
+/// The number of nodes along each axis of a block of the grid
+pub const BLOCK_SIZE: usize = 4;
+
+/// The number of nodes in a block of the grid
+const BLOCK_NUM_NODES: usize = BLOCK_SIZE * BLOCK_SIZE * BLOCK_SIZE;
+
+/// The largest number of cells a CPDI domain can span along an axis before falling back to GIMP
+pub const MAX_CPDI_SPAN: usize = 8;
+
 /// The Grid of Node in Lagrangian space
+///
+/// The grid is sparse: nodes are allocated by blocks of `BLOCK_SIZE`^3, and only the blocks
+/// touched since the last `clear` are active. `get_node_mut` activates the block of a node
+/// when needed, and grid-wide systems only go through the `nodes` of the active blocks.
+///
+/// The boundaries and fixed temperatures are still stored for every node of the grid, active
+/// or not, so that they can be set up front and survive `clear`. Their memory grows with the
+/// full size of the grid.
 #[derive(Debug)]
 pub struct Grid {
   /// The distance between each pair of neighbor nodes
-  pub dx: f32,
+  pub dx: Float,
 
   /// Dimension vector; the number of nodes along each axis
   pub dim: Vector3u,
 
-  /// Nodes Array
+  /// The world-space position of the first node, i.e. the minimum corner of the grid
+  pub origin: Vector3f,
+
+  /// The nodes of the active blocks, block after block in the order they were activated
   pub nodes: Vec<Node>,
+
+  /// The active blocks, by their block index
+  blocks: Vec<Vector3u>,
+
+  /// The number of blocks along each axis
+  block_dim: Vector3u,
+
+  /// The position of each block in `blocks`, `None` if the block is not active
+  block_table: Vec<Option<usize>>,
+
+  /// The boundary of every node of the grid, active or not
+  boundaries: Vec<Boundary>,
+
+  /// The fixed temperature of every node of the grid, active or not
+  boundary_temperatures: Vec<Option<Float>>,
+
+  /// The node returned for nodes outside of the active blocks
+  empty_node: Node,
+
+  /// The interpolation kernel used to compute weights
+  pub kernel: Box<dyn Kernel>,
+
+  /// How particle domains are taken into account when computing weights
+  pub shape_function: ShapeFunction,
+
+  /// The Coulomb friction between bodies when each body has its own velocity field,
+  /// `None` if all the bodies share the same field
+  pub contact_friction: Option<Float>,
+
+  /// Whether the grid is a single layer of cells along z for a 2D world. The velocities are
+  /// then kept in the xy plane, i.e. plane strain
+  pub planar: bool,
 }
 
 impl Default for Grid {
@@ -192,74 +413,360 @@ impl Default for Grid {
 impl Grid {
   /// Create a new grid using `dimension` and `dx`. All nodes will be initialized
   /// to initial `0` values.
-  pub fn new(dim: Vector3u, dx: f32) -> Self {
+  pub fn new(dim: Vector3u, dx: Float) -> Self {
     let num_nodes = dim.x * dim.y * dim.z;
-    let nodes = vec![Node::new(); num_nodes];
-    Self { dx, dim, nodes }
+    let block_dim = dim.map(|d| d.div_ceil(BLOCK_SIZE));
+    let kernel = Box::new(QuadraticKernel);
+    let shape_function = ShapeFunction::Kernel;
+    Self {
+      dx,
+      dim,
+      origin: Vector3f::zeros(),
+      nodes: Vec::new(),
+      blocks: Vec::new(),
+      block_dim,
+      block_table: vec![None; block_dim.x * block_dim.y * block_dim.z],
+      boundaries: vec![Boundary::None; num_nodes],
+      boundary_temperatures: vec![None; num_nodes],
+      empty_node: Node::new(),
+      kernel,
+      shape_function,
+      contact_friction: None,
+      planar: false,
+    }
+  }
+
+  /// Create a grid of a 2D world with `dim` nodes along x and y. The particles lie in the
+  /// plane `z = plane_z()`, in the middle of the 3 layers of nodes along z
+  pub fn new_2d(dim: Vector2u, dx: Float) -> Self {
+    let mut grid = Self::new(Vector3u::new(dim.x, dim.y, 3), dx);
+    grid.planar = true;
+    grid
+  }
+
+  /// The z of the plane the particles of a 2D grid lie in
+  pub fn plane_z(&self) -> Float {
+    self.origin.z + self.dx
+  }
+
+  /// Keep a velocity in the xy plane if the grid is planar
+  pub fn project_planar(&self, velocity: Vector3f) -> Vector3f {
+    if self.planar {
+      Vector3f::new(velocity.x, velocity.y, 0.0)
+    } else {
+      velocity
+    }
+  }
+
+  /// Move the grid so that its first node is at `origin` instead of the world origin
+  pub fn with_origin(mut self, origin: Vector3f) -> Self {
+    self.origin = origin;
+    self
+  }
+
+  /// Use the given interpolation kernel instead of the default quadratic B-spline
+  pub fn with_kernel(mut self, kernel: Box<dyn Kernel>) -> Self {
+    self.kernel = kernel;
+    self
+  }
+
+  /// Use the given shape function when computing weights of particles
+  pub fn with_shape_function(mut self, shape_function: ShapeFunction) -> Self {
+    self.shape_function = shape_function;
+    self
+  }
+
+  /// Give each body its own velocity field, with contact resolved between the fields using
+  /// the given friction coefficient
+  pub fn with_body_contact(mut self, friction: Float) -> Self {
+    self.contact_friction = Some(friction);
+    self
+  }
+
+  /// The velocity field used by a particle of `body`, `None` for the shared field
+  pub fn field_of(&self, body: Option<usize>) -> Option<usize> {
+    self.contact_friction.and(body)
+  }
+
+  /// Add a force to a node, and to the velocity field of `body` if any
+  pub fn add_force(&mut self, node_index: Vector3u, body: Option<usize>, force: Vector3f) {
+    self.get_node_mut(node_index).add_force(body, force);
+  }
+
+  /// Check if the node index is inside of the grid
+  fn contains_index(&self, node_index: Vector3i) -> bool {
+    let x_in = 0 <= node_index.x && node_index.x < self.dim.x as i32;
+    let y_in = 0 <= node_index.y && node_index.y < self.dim.y as i32;
+    let z_in = 0 <= node_index.z && node_index.z < self.dim.z as i32;
+    x_in && y_in && z_in
   }
 
   /// Get the overall size of this grid
   pub fn size(&self) -> Vector3f {
-    Vector3f::new(self.dim.x as f32, self.dim.y as f32, self.dim.z as f32) * self.dx
+    Vector3f::new(self.dim.x as Float, self.dim.y as Float, self.dim.z as Float) * self.dx
+  }
+
+  /// Get the world-space box covered by this grid
+  pub fn bounds(&self) -> BoundingBox {
+    BoundingBox::new_from_vec(self.origin, self.origin + self.size())
   }
 
-  /// Get the raw index inside the `nodes` array from `Vector3i`
-  fn raw_index(&self, node_index: Vector3u) -> usize {
+  /// The index of a node among all the nodes of the grid, active or not
+  fn dense_index(&self, node_index: Vector3u) -> usize {
     let z_comp = self.dim.x * self.dim.y * node_index.z;
     let y_comp = self.dim.x * node_index.y;
     let x_comp = node_index.x;
     z_comp + y_comp + x_comp
   }
 
-  /// Get the node using `Vector3i` node index
+  /// The index of a block in `block_table`
+  fn block_table_index(block_dim: Vector3u, block: Vector3u) -> usize {
+    (block.z * block_dim.y + block.y) * block_dim.x + block.x
+  }
+
+  /// The index of a node within its block
+  fn local_index(node_index: Vector3u) -> usize {
+    let local = node_index.map(|i| i % BLOCK_SIZE);
+    (local.z * BLOCK_SIZE + local.y) * BLOCK_SIZE + local.x
+  }
+
+  /// The offset of a node from the first node of its block, given its index within the block
+  fn local_offset(local_index: usize) -> Vector3u {
+    Vector3u::new(
+      local_index % BLOCK_SIZE,
+      local_index / BLOCK_SIZE % BLOCK_SIZE,
+      local_index / (BLOCK_SIZE * BLOCK_SIZE),
+    )
+  }
+
+  /// Get the raw index inside the `nodes` array from `Vector3u`, `None` if the node is not
+  /// in an active block. Raw indices are only valid until the next `clear`, after which the
+  /// blocks may be activated in another order
+  pub fn raw_index(&self, node_index: Vector3u) -> Option<usize> {
+    let block = node_index.map(|i| i / BLOCK_SIZE);
+    self.block_table[Self::block_table_index(self.block_dim, block)]
+      .map(|slot| slot * BLOCK_NUM_NODES + Self::local_index(node_index))
+  }
+
+  /// Get the node index of the node at a raw index of the `nodes` array
+  pub fn node_index(&self, raw_index: usize) -> Vector3u {
+    let block = self.blocks[raw_index / BLOCK_NUM_NODES];
+    block * BLOCK_SIZE + Self::local_offset(raw_index % BLOCK_NUM_NODES)
+  }
+
+  /// Get the node using `Vector3u` node index. Nodes outside of the active blocks are empty
   pub fn get_node(&self, node_index: Vector3u) -> &Node {
-    let index = self.raw_index(node_index);
-    &self.nodes[index]
+    match self.raw_index(node_index) {
+      Some(index) => &self.nodes[index],
+      None => &self.empty_node,
+    }
   }
 
-  /// Get the mutable node using `Vector3i` node index
+  /// Get the node at a possibly out of range index, `None` if it is outside of the grid or
+  /// not in an active block
+  pub fn get_node_checked(&self, node_index: Vector3i) -> Option<&Node> {
+    if self.contains_index(node_index) {
+      let uindex = Vector3u::new(node_index.x as usize, node_index.y as usize, node_index.z as usize);
+      self.raw_index(uindex).map(|index| &self.nodes[index])
+    } else {
+      None
+    }
+  }
+
+  /// Get the mutable node using `Vector3u` node index, activating its block if needed
   pub fn get_node_mut(&mut self, node_index: Vector3u) -> &mut Node {
-    let index = self.raw_index(node_index);
+    let index = self.activate(node_index);
     &mut self.nodes[index]
   }
 
-  /// Get the node position
-  pub fn node_position(&self, node_index: Vector3u) -> Vector3f {
-    let v = Vector3f::new(node_index.x as f32, node_index.y as f32, node_index.z as f32);
-    v * self.dx
+  /// Get the raw index of a node, activating its block if needed
+  fn activate(&mut self, node_index: Vector3u) -> usize {
+    match self.raw_index(node_index) {
+      Some(index) => index,
+      None => self.activate_block(node_index.map(|i| i / BLOCK_SIZE)) * BLOCK_NUM_NODES + Self::local_index(node_index),
+    }
   }
 
-  /// Get 1d weight given position. Will normalize `pos` to index space.
+  /// Add the values of many particles to their neighbor nodes in parallel. `add` is given the
+  /// node, its position, the weight and the weight gradient of the particle, and the particle.
   ///
-  /// Returns the base node index, the weights of the three nodes, and the
-  /// weight gradients of the three nodes.
-  fn get_weight_1d(&self, pos: f32) -> (i32, Vector3f, Vector3f) {
-    // `x` is normalized to index space
-    let x = pos / self.dx;
+  /// The particles are binned by the block of their first neighbor node, so that a particle only
+  /// touches its block and the next one along each axis. The blocks are then processed in 8
+  /// passes by the parity of their index: the blocks of a pass touch disjoint nodes, so they add
+  /// their particles in parallel. The few particles spreading over more blocks (e.g. stretched
+  /// CPDI domains) are added afterwards. The sums only differ from a serial loop by their order.
+  pub fn scatter<T, F>(&mut self, particles: &[ScatterParticle<T>], add: F)
+  where
+    T: Sync,
+    F: Fn(&mut Node, Vector3f, Float, Vector3f, &ScatterParticle<T>) + Sync,
+  {
+    // Activate the blocks of the neighbors and bin the particles with their weights
+    let weights: Vec<_> = particles
+      .par_iter()
+      .map(|p| self.particle_weights(p.position, p.volume, &p.f))
+      .collect();
+    let mut bins: Vec<Vec<(usize, WeightIterator)>> = vec![];
+    let mut spread = vec![];
+    for (i, weights) in weights.into_iter().enumerate() {
+      let (min, max) = match weights.bounds() {
+        Some(bounds) => bounds,
+        None => continue,
+      };
+      let (min_block, max_block) = (min.map(|i| i / BLOCK_SIZE), max.map(|i| i / BLOCK_SIZE));
+      for z in min_block.z..=max_block.z {
+        for y in min_block.y..=max_block.y {
+          for x in min_block.x..=max_block.x {
+            self.activate(Vector3u::new(x, y, z) * BLOCK_SIZE);
+          }
+        }
+      }
+      if (max_block - min_block).iter().all(|&d| d <= 1) {
+        let slot = self.block_table[Self::block_table_index(self.block_dim, min_block)].unwrap();
+        bins.resize_with(bins.len().max(slot + 1), Vec::new);
+        bins[slot].push((i, weights));
+      } else {
+        spread.push((i, weights));
+      }
+    }
+
+    // Take the nodes out so that the positions of the nodes can still be read during the passes
+    let mut nodes = std::mem::take(&mut self.nodes);
+    for parity in 0..8 {
+      // Each block of this parity owns the nodes of itself and of its next blocks
+      let mut chunks: Vec<_> = nodes.chunks_mut(BLOCK_NUM_NODES).map(Some).collect();
+      let mut groups = vec![];
+      for (slot, bin) in bins.iter_mut().enumerate() {
+        let block = self.blocks[slot];
+        let block_parity = (block.x & 1) | (block.y & 1) << 1 | (block.z & 1) << 2;
+        if bin.is_empty() || block_parity != parity {
+          continue;
+        }
+        let mut neighbors: [Option<&mut [Node]>; 8] = Default::default();
+        for (n, neighbor) in neighbors.iter_mut().enumerate() {
+          let next = block + Vector3u::new(n & 1, n >> 1 & 1, n >> 2 & 1);
+          if next.x < self.block_dim.x && next.y < self.block_dim.y && next.z < self.block_dim.z {
+            if let Some(next_slot) = self.block_table[Self::block_table_index(self.block_dim, next)] {
+              *neighbor = chunks[next_slot].take();
+            }
+          }
+        }
+        groups.push((block, std::mem::take(bin), neighbors));
+      }
+
+      groups.into_par_iter().for_each(|(block, bin, mut neighbors)| {
+        for (i, weights) in bin {
+          let p = &particles[i];
+          for (node_index, weight, grad_w) in weights {
+            let next = node_index.map(|i| i / BLOCK_SIZE) - block;
+            let chunk = neighbors[next.x | next.y << 1 | next.z << 2].as_mut().unwrap();
+            add(
+              &mut chunk[Self::local_index(node_index)],
+              self.node_position(node_index),
+              weight,
+              grad_w,
+              p,
+            );
+          }
+        }
+      });
+    }
+    self.nodes = nodes;
+
+    for (i, weights) in spread {
+      let p = &particles[i];
+      for (node_index, weight, grad_w) in weights {
+        let node_position = self.node_position(node_index);
+        add(self.get_node_mut(node_index), node_position, weight, grad_w, p);
+      }
+    }
+  }
+
+  /// Allocate the nodes of a block, with their boundaries. Returns the position of the block
+  fn activate_block(&mut self, block: Vector3u) -> usize {
+    let slot = self.blocks.len();
+    self.block_table[Self::block_table_index(self.block_dim, block)] = Some(slot);
+    self.blocks.push(block);
+    for local in 0..BLOCK_NUM_NODES {
+      let node_index = block * BLOCK_SIZE + Self::local_offset(local);
+      let mut node = Node::new();
+      if node_index.x < self.dim.x && node_index.y < self.dim.y && node_index.z < self.dim.z {
+        let index = self.dense_index(node_index);
+        node.boundary = self.boundaries[index];
+        node.boundary_temperature = self.boundary_temperatures[index];
+      }
+      self.nodes.push(node);
+    }
+    slot
+  }
 
-    // get the base node around `x`
-    let base_node = (x - 0.5).floor();
+  /// Deactivate all the blocks, emptying the grid
+  pub fn clear(&mut self) {
+    for block in &self.blocks {
+      self.block_table[Self::block_table_index(self.block_dim, *block)] = None;
+    }
+    self.blocks.clear();
+    self.nodes.clear();
+  }
 
-    let mut w = Vector3f::zeros();
-    let mut dw = Vector3f::zeros();
+  /// The number of active blocks
+  pub fn num_active_blocks(&self) -> usize {
+    self.blocks.len()
+  }
 
-    let d0 = x - base_node;
-    let z = 1.5 - d0;
-    let z2 = z * z;
-    w.x = 0.5 * z2;
-    dw.x = -z;
+  /// The boundary of a node, whether it is active or not
+  pub fn boundary(&self, node_index: Vector3u) -> Boundary {
+    self.boundaries[self.dense_index(node_index)]
+  }
 
-    let d1 = d0 - 1.0;
-    w.y = 0.75 - d1 * d1;
-    dw.y = -2.0 * d1;
+  /// Set the boundary of a node. It is kept when the grid is cleared
+  pub fn set_boundary(&mut self, node_index: Vector3u, boundary: Boundary) {
+    let index = self.dense_index(node_index);
+    self.boundaries[index] = boundary;
+    if let Some(raw) = self.raw_index(node_index) {
+      self.nodes[raw].boundary = boundary;
+    }
+  }
 
-    let d2 = 1.0 - d1;
-    let zz = 1.5 - d2;
-    let zz2 = zz * zz;
-    w.z = 0.5 * zz2;
-    dw.z = zz;
+  /// The fixed temperature of a node at a possibly out of range index, whether it is active
+  /// or not. `None` if it is outside of the grid or has no fixed temperature
+  pub fn boundary_temperature(&self, node_index: Vector3i) -> Option<Float> {
+    if self.contains_index(node_index) {
+      let uindex = Vector3u::new(node_index.x as usize, node_index.y as usize, node_index.z as usize);
+      self.boundary_temperatures[self.dense_index(uindex)]
+    } else {
+      None
+    }
+  }
 
-    (base_node as i32, w, dw)
+  /// Set the fixed temperature of a node. It is kept when the grid is cleared
+  pub fn set_boundary_temperature(&mut self, node_index: Vector3u, temperature: Option<Float>) {
+    let index = self.dense_index(node_index);
+    self.boundary_temperatures[index] = temperature;
+    if let Some(raw) = self.raw_index(node_index) {
+      self.nodes[raw].boundary_temperature = temperature;
+    }
+  }
+
+  /// Get the node position
+  pub fn node_position(&self, node_index: Vector3u) -> Vector3f {
+    let v = Vector3f::new(node_index.x as Float, node_index.y as Float, node_index.z as Float);
+    self.origin + v * self.dx
+  }
+
+  /// The position of a point in index space, where node `i` is at `i`
+  pub fn index_position(&self, pos: Vector3f) -> Vector3f {
+    (pos - self.origin) / self.dx
+  }
+
+  /// Get the inverse of the inertia-like tensor $D_p^{-1}$ used by APIC, e.g.
+  /// $D_p = \frac{1}{4} \Delta x^2 I$ for the quadratic B-spline. `None` if the kernel
+  /// does not have a constant $D_p$, or if particle domains are used; the weight
+  /// gradient should be used instead.
+  pub fn apic_inverse_inertia(&self) -> Option<Float> {
+    match self.shape_function {
+      ShapeFunction::Kernel => self.kernel.inertia().map(|k| 1.0 / (k * self.dx * self.dx)),
+      _ => None,
+    }
   }
 
   /// Iterate the neighbors. Will get `node_index`, `weight` and `weight_gradient`.
@@ -277,16 +784,55 @@ impl Grid {
   /// }
   /// ```
   pub fn neighbor_weights(&self, pos: Vector3f) -> WeightIterator {
-    let (bnx, wx, dwx) = self.get_weight_1d(pos.x);
-    let (bny, wy, dwy) = self.get_weight_1d(pos.y);
-    let (bnz, wz, dwz) = self.get_weight_1d(pos.z);
+    self.kernel_weights(&*self.kernel, pos)
+  }
+
+  /// Iterate the neighbors of a particle taking its domain into account according
+  /// to the grid's `shape_function`. The particle initially occupies a cube of
+  /// `volume` centered at `pos`, which is deformed by `f` for CPDI. Same as
+  /// `neighbor_weights` when the shape function is `ShapeFunction::Kernel`.
+  pub fn particle_weights(&self, pos: Vector3f, volume: Float, f: &Matrix3f) -> WeightIterator {
+    match self.shape_function {
+      ShapeFunction::Kernel => self.neighbor_weights(pos),
+      ShapeFunction::Gimp => self.gimp_weights(pos, volume),
+      ShapeFunction::Cpdi => self.cpdi_weights(pos, volume, f),
+    }
+  }
+
+  /// The gradient of the grid velocity $\nabla v_p = \sum_i v_i \nabla w_{ip}^T$ given the
+  /// weights around a particle, using the velocity field of `body` if any
+  pub fn velocity_gradient(&self, weights: WeightIterator, body: Option<usize>) -> Matrix3f {
+    let mut grad_vp = Matrix3f::zeros();
+    for (node_index, _, grad_w) in weights {
+      grad_vp += self.get_node(node_index).velocities(body).1 * grad_w.transpose();
+    }
+    grad_vp
+  }
+
+  /// Same as `velocity_gradient` with the velocity before the grid update, $v_i^n$. Used by
+  /// the forces, which are computed before the updated velocity is known
+  pub fn velocity_temp_gradient(&self, weights: WeightIterator, body: Option<usize>) -> Matrix3f {
+    let mut grad_vp = Matrix3f::zeros();
+    for (node_index, _, grad_w) in weights {
+      grad_vp += self.get_node(node_index).velocities(body).0 * grad_w.transpose();
+    }
+    grad_vp
+  }
+
+  fn kernel_weights(&self, kernel: &dyn Kernel, pos: Vector3f) -> WeightIterator {
+    let x = self.index_position(pos);
+    let (bnx, wx, dwx) = kernel.weights_1d(x.x);
+    let (bny, wy, dwy) = kernel.weights_1d(x.y);
+    let (bnz, wz, dwz) = kernel.weights_1d(x.z);
     let dx = self.dx;
     let dim = self.dim;
+    let support = kernel.support();
     let base_node = Vector3i::new(bnx, bny, bnz);
     let curr_node = Vector3i::zeros();
-    WeightIterator {
+    let it = TensorWeightIterator {
       dx,
       dim,
+      support,
       base_node,
       curr_node,
       wx,
@@ -295,6 +841,91 @@ impl Grid {
       dwx,
       dwy,
       dwz,
+    };
+    WeightIterator {
+      weights: Weights::Tensor(it),
+    }
+  }
+
+  /// GIMP weights of a particle occupying a cube of `volume` centered at `pos`
+  fn gimp_weights(&self, pos: Vector3f, volume: Float) -> WeightIterator {
+    let kernel = GimpKernel::new(0.5 * volume.cbrt() / self.dx);
+    self.kernel_weights(&kernel, pos)
+  }
+
+  /// CPDI1 weights. With the domain spanned by $r_k = F (l_p e_k)$, the weight and its
+  /// gradient are averaged over the 8 corners $x_c = x_p \pm r_1 \pm r_2 \pm r_3$:
+  ///
+  /// $$w_{ip} = \frac{1}{8} \sum_c N_i(x_c)$$
+  /// $$\nabla w_{ip} = \frac{1}{8} \sum_c N_i(x_c) J^{-T} s_c$$
+  ///
+  /// where $J = [r_1\ r_2\ r_3]$, $s_c$ is the sign vector of the corner and $N_i$ is linear.
+  ///
+  /// Falls back to the GIMP weights of the undeformed domain when the domain is degenerate, as
+  /// the gradients are then undefined, or spans more than `MAX_CPDI_SPAN` cells along an axis.
+  fn cpdi_weights(&self, pos: Vector3f, volume: Float, f: &Matrix3f) -> WeightIterator {
+    let half_size = 0.5 * volume.cbrt();
+    let domain = f * half_size;
+
+    // Corners in index space and the base nodes of their linear stencils
+    let mut corners = [(Vector3f::zeros(), Vector3f::zeros()); 8];
+    let mut min = Vector3i::new(i32::MAX, i32::MAX, i32::MAX);
+    let mut max = Vector3i::new(i32::MIN, i32::MIN, i32::MIN);
+    for (c, corner) in corners.iter_mut().enumerate() {
+      let sign = Vector3f::new(
+        if c & 1 == 0 { -1.0 } else { 1.0 },
+        if c & 2 == 0 { -1.0 } else { 1.0 },
+        if c & 4 == 0 { -1.0 } else { 1.0 },
+      );
+      let x = self.index_position(pos + domain * sign);
+      let base = x.map(|v| v.floor() as i32);
+      min = min.zip_map(&base, i32::min);
+      max = max.zip_map(&base, i32::max);
+      *corner = (x, sign);
+    }
+    let span = max.zip_map(&min, i32::saturating_sub);
+    let bounded = span.iter().all(|&s| s < MAX_CPDI_SPAN as i32);
+    let domain_inv_t = match domain.try_inverse() {
+      Some(inv) if bounded && f.determinant().abs() > 1e-6 => inv.transpose(),
+      _ => return self.gimp_weights(pos, volume),
+    };
+
+    // Accumulate into the box of nodes covered by all the corners
+    let size = (max - min).map(|v| v as usize + 2);
+    let mut acc = vec![(0.0, Vector3f::zeros()); size.x * size.y * size.z];
+    for (x, sign) in corners.iter() {
+      let (bnx, wx, _) = LinearKernel.weights_1d(x.x);
+      let (bny, wy, _) = LinearKernel.weights_1d(x.y);
+      let (bnz, wz, _) = LinearKernel.weights_1d(x.z);
+      let grad_dir = domain_inv_t * sign;
+      for (k, wk) in wz.iter().take(2).enumerate() {
+        for (j, wj) in wy.iter().take(2).enumerate() {
+          for (i, wi) in wx.iter().take(2).enumerate() {
+            let w = wi * wj * wk / 8.0;
+            let local = Vector3i::new(bnx + i as i32, bny + j as i32, bnz + k as i32) - min;
+            let index = (local.z as usize * size.y + local.y as usize) * size.x + local.x as usize;
+            acc[index].0 += w;
+            acc[index].1 += w * grad_dir;
+          }
+        }
+      }
+    }
+
+    // Collect the non-zero weights of the nodes inside the grid
+    let mut weights = Vec::with_capacity(acc.len());
+    for (index, (w, grad_w)) in acc.into_iter().enumerate() {
+      let local = Vector3i::new(
+        (index % size.x) as i32,
+        (index / size.x % size.y) as i32,
+        (index / (size.x * size.y)) as i32,
+      );
+      let node_index = min + local;
+      if w > 0.0 && self.contains_index(node_index) {
+        weights.push((node_index.map(|v| v as usize), w, grad_w));
+      }
+    }
+    WeightIterator {
+      weights: Weights::List(weights.into_iter()),
     }
   }
 
@@ -0,0 +1,38 @@
+use crate::utils::*;
+
+/// The time integrator of the grid velocity update
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub enum Integrator {
+  /// Symplectic Euler. The elastic force is evaluated at the beginning of the step
+  #[default]
+  Explicit,
+
+  /// Backward Euler on the elastic force, solved by Newton iterations with a matrix-free
+  /// conjugate gradient on the Hessian of the incremental potential. Allows much larger
+  /// time steps for stiff materials. Only used by `Pipeline::Standard`
+  Implicit {
+    /// Maximum number of Newton iterations per step
+    newton_iterations: usize,
+
+    /// Maximum number of conjugate gradient iterations per Newton iteration
+    cg_iterations: usize,
+
+    /// Relative tolerance of the residual, for both the Newton and the CG iterations
+    tolerance: Float,
+  },
+}
+
+impl Integrator {
+  /// The implicit integrator with default settings
+  pub fn implicit() -> Self {
+    Integrator::Implicit {
+      newton_iterations: 5,
+      cg_iterations: 100,
+      tolerance: 1e-3,
+    }
+  }
+
+  pub fn is_implicit(&self) -> bool {
+    matches!(self, Integrator::Implicit { .. })
+  }
+}
@@ -1,9 +1,21 @@
 mod consts;
 mod delta_time;
+mod flip_ratio;
 mod grid;
+mod integrator;
+mod pipeline;
+mod simulation_time;
 mod step_count;
+mod time_step;
+mod transfer_scheme;
 
 pub use consts::*;
 pub use delta_time::*;
+pub use flip_ratio::*;
 pub use grid::*;
+pub use integrator::*;
+pub use pipeline::*;
+pub use simulation_time::*;
 pub use step_count::*;
+pub use time_step::*;
+pub use transfer_scheme::*;
@@ -0,0 +1,13 @@
+/// The set of systems the world steps with
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub enum Pipeline {
+  /// Classic MPM. The elastic force is computed in a separate pass
+  /// (`ApplyElasticitySystem`) after the particle to grid transfer
+  #[default]
+  Standard,
+
+  /// Moving Least Squares MPM. The stress is folded into the particle to
+  /// grid transfer (`MlsP2GSystem`) and the deformation gradient is evolved
+  /// from the APIC affine velocity. Always uses `TransferScheme::Apic`
+  MlsMpm,
+}
@@ -0,0 +1,65 @@
+use crate::utils::*;
+
+/// The simulated time, advanced by the dt of every step, and the frames advanced so far
+/// by `World::advance_frame`
+#[derive(Default)]
+pub struct SimulationTime {
+  time: Float,
+  frame: usize,
+  frame_dt: Option<Float>,
+  frame_time: Float,
+  frame_end: bool,
+}
+
+impl SimulationTime {
+  /// The time at the end of the last step
+  pub fn get(&self) -> Float {
+    self.time
+  }
+
+  /// The number of frames finished
+  pub fn frame(&self) -> usize {
+    self.frame
+  }
+
+  /// Whether the last step finished a frame
+  pub fn is_frame_end(&self) -> bool {
+    self.frame_end
+  }
+
+  /// Whether a frame started by `begin_frame` is not finished yet
+  pub fn in_frame(&self) -> bool {
+    self.frame_dt.is_some()
+  }
+
+  /// Start a frame of length `frame_dt`, which the following steps will land exactly on
+  pub fn begin_frame(&mut self, frame_dt: Float) {
+    self.frame_dt = Some(frame_dt);
+    self.frame_time = 0.0;
+  }
+
+  /// Advance the time by a step of `dt`, shortened so that it does not cross the end of
+  /// the current frame. When the frame is almost over, the rest is split in two instead of
+  /// leaving a tiny last step. Returns the dt actually taken
+  pub fn advance(&mut self, dt: Float) -> Float {
+    self.frame_end = false;
+    let dt = match self.frame_dt {
+      Some(frame_dt) => {
+        let remaining = frame_dt - self.frame_time;
+        if dt >= remaining {
+          self.frame_dt = None;
+          self.frame += 1;
+          self.frame_end = true;
+          remaining
+        } else {
+          let dt = if 2.0 * dt > remaining { 0.5 * remaining } else { dt };
+          self.frame_time += dt;
+          dt
+        }
+      }
+      None => dt,
+    };
+    self.time += dt;
+    dt
+  }
+}
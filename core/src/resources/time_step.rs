@@ -0,0 +1,41 @@
+use crate::utils::*;
+
+/// How the `DeltaTime` of each step is chosen
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub enum TimeStep {
+  /// Keep the `DeltaTime` set by `WorldBuilder::with_dt` or `World::set_dt`
+  #[default]
+  Fixed,
+
+  /// Recompute `DeltaTime` before every step from the CFL condition
+  ///
+  /// $$\Delta t = C \frac{\Delta x}{\max_p (\|v_p\| + c_p)}$$
+  ///
+  /// where $c_p$ is the elastic (or acoustic) wave speed of the particle, clamped to
+  /// `[min_dt, max_dt]`. The chosen value can be read back from `DeltaTime`
+  Adaptive {
+    /// C, the CFL number
+    cfl: Float,
+
+    /// The smallest time step ever taken
+    min_dt: Float,
+
+    /// The largest time step ever taken, also used when there is nothing moving
+    max_dt: Float,
+  },
+}
+
+impl TimeStep {
+  /// Adaptive time stepping between `min_dt` and `max_dt` with a CFL number of 0.5
+  pub fn adaptive(min_dt: Float, max_dt: Float) -> Self {
+    TimeStep::Adaptive {
+      cfl: 0.5,
+      min_dt,
+      max_dt,
+    }
+  }
+
+  pub fn is_adaptive(&self) -> bool {
+    matches!(self, TimeStep::Adaptive { .. })
+  }
+}
@@ -0,0 +1,11 @@
+/// The scheme used to transfer velocities between particles and the grid
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub enum TransferScheme {
+  /// Blend of PIC and FLIP velocities in G2P
+  #[default]
+  PicFlip,
+
+  /// Affine Particle-In-Cell. Particles carry an affine velocity matrix
+  /// (`ParticleAffineVelocity`) which preserves angular momentum
+  Apic,
+}
@@ -0,0 +1,73 @@
+use rayon::prelude::*;
+use specs::prelude::*;
+
+use crate::components::*;
+use crate::resources::*;
+use crate::utils::*;
+
+/// Choose the `DeltaTime` of the coming step when the `TimeStep` is adaptive
+pub struct AdaptDeltaTimeSystem;
+
+impl<'a> System<'a> for AdaptDeltaTimeSystem {
+  type SystemData = (
+    Entities<'a>,
+    Read<'a, TimeStep>,
+    Read<'a, Grid>,
+    Write<'a, DeltaTime>,
+    ReadStorage<'a, ParticleVelocity>,
+    ReadStorage<'a, ParticleMass>,
+    ReadStorage<'a, ParticleVolume>,
+    ReadStorage<'a, ParticleDeformation>,
+    ReadStorage<'a, ParticleFluid>,
+  );
+
+  fn run(
+    &mut self,
+    (entities, time_step, grid, mut dt, velocities, masses, volumes, deformations, fluids): Self::SystemData,
+  ) {
+    if let TimeStep::Adaptive { cfl, min_dt, max_dt } = *time_step {
+      // The fastest speed information travels at among all the particles
+      let max_speed = (&entities, &velocities, &masses)
+        .par_join()
+        .map(|(entity, velocity, mass)| {
+          let volume = volumes.get(entity).map_or(0.0, ParticleVolume::get);
+          let speed =
+            velocity.get().norm() + wave_speed(mass.get(), volume, deformations.get(entity), fluids.get(entity));
+
+          // `Float::max` would drop a NaN speed in the reduction
+          if speed.is_nan() {
+            Float::INFINITY
+          } else {
+            speed
+          }
+        })
+        .reduce(|| 0.0, Float::max);
+
+      // A blown up particle gives a non finite speed, in which case we take the smallest step
+      let new_dt = if max_speed.is_finite() {
+        if max_speed > 0.0 {
+          cfl * grid.dx / max_speed
+        } else {
+          max_dt
+        }
+      } else {
+        min_dt
+      };
+      dt.set(new_dt.max(min_dt).min(max_dt));
+    }
+  }
+}
+
+/// The speed of the pressure wave $\sqrt{(\lambda + 2 \mu) / \rho}$ of a solid particle, or
+/// $\sqrt{K J^{-\gamma} / \rho}$ of a fluid particle. Particles without volume have no wave speed
+fn wave_speed(mass: Float, volume: Float, def: Option<&ParticleDeformation>, fluid: Option<&ParticleFluid>) -> Float {
+  if mass == 0.0 || volume == 0.0 {
+    return 0.0;
+  }
+  let solid_modulus = def.map_or(0.0, |def| {
+    let (mu, lambda) = def.lame_parameters();
+    lambda + 2.0 * mu
+  });
+  let fluid_modulus = fluid.map_or(0.0, |fluid| fluid.bulk_modulus * fluid.j.powf(1.0 - fluid.gamma));
+  ((solid_modulus + fluid_modulus) * volume / mass).sqrt()
+}
@@ -0,0 +1,16 @@
+use specs::prelude::*;
+
+use crate::resources::*;
+
+/// Advance the `SimulationTime` by the `DeltaTime` of the step, which is shortened when it
+/// would cross the end of the current frame
+pub struct AdvanceTimeSystem;
+
+impl<'a> System<'a> for AdvanceTimeSystem {
+  type SystemData = (Write<'a, DeltaTime>, Write<'a, SimulationTime>);
+
+  fn run(&mut self, (mut dt, mut time): Self::SystemData) {
+    let step_dt = time.advance(dt.get());
+    dt.set(step_dt);
+  }
+}
@@ -0,0 +1,43 @@
+use rayon::prelude::*;
+use specs::prelude::*;
+
+use crate::components::*;
+use crate::resources::*;
+
+/// Scatter the pressure and viscous forces of fluid particles to the grid. The
+/// force on node $i$ is $-V_p^0 J_p \sigma_p \nabla w_{ip}$
+pub struct ApplyFluidStressSystem;
+
+impl<'a> System<'a> for ApplyFluidStressSystem {
+  type SystemData = (
+    Entities<'a>,
+    Write<'a, Grid>,
+    ReadStorage<'a, ParticlePosition>,
+    ReadStorage<'a, ParticleVolume>,
+    ReadStorage<'a, ParticleFluid>,
+    ReadStorage<'a, ParticleBody>,
+  );
+
+  fn run(&mut self, (entities, mut grid, positions, volumes, fluids, bodies): Self::SystemData) {
+    let particles: Vec<_> = (&entities, &positions, &volumes, &fluids)
+      .par_join()
+      .map(|(entity, position, volume, fluid)| {
+        let body = grid.field_of(bodies.get(entity).map(ParticleBody::get));
+        let f = fluid.deformation_gradient();
+        let grad_vp = grid.velocity_temp_gradient(grid.particle_weights(position.get(), volume.get(), &f), body);
+        let stress = volume.get() * fluid.j * fluid.cauchy_stress(&grad_vp);
+        ScatterParticle {
+          position: position.get(),
+          volume: volume.get(),
+          f,
+          data: (stress, body),
+        }
+      })
+      .collect();
+
+    grid.scatter(&particles, |node, _, _, grad_w, p| {
+      let (stress, body) = p.data;
+      node.add_force(body, -stress * grad_w);
+    });
+  }
+}
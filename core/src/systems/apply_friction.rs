@@ -6,26 +6,39 @@ use crate::utils::*; // Grid
 
 pub struct ApplyFrictionSystem;
 
+impl ApplyFrictionSystem {
+  /// The friction force of a boundary with the given `normal` and `mu` on a node (or a body
+  /// field of the node) of the given mass, velocity and force
+  fn friction(normal: Vector3f, mu: Float, mass: Float, velocity: Vector3f, force: Vector3f, dt: Float) -> Vector3f {
+    let norm_vel = Vector3f::dot(&normal, &velocity) * normal;
+    let tan_vel = velocity - norm_vel;
+
+    // Make sure that we have velocity in tangent velocity direction
+    if tan_vel.magnitude() > Float::EPSILON {
+      // Calculate friction force magnitude
+      let normal_force_mag = -Float::min(Vector3f::dot(&normal, &force), 0.0);
+      let max_force_mag = mass * tan_vel.magnitude() / dt;
+      let fric_force_mag = Float::min(normal_force_mag * mu, max_force_mag);
+
+      // Friction force direction is the opposite of tangent velocity
+      let fric_force_dir = -tan_vel.normalize();
+
+      fric_force_dir * fric_force_mag
+    } else {
+      Vector3f::zeros()
+    }
+  }
+}
+
 impl<'a> System<'a> for ApplyFrictionSystem {
   type SystemData = (Read<'a, DeltaTime>, Write<'a, Grid>);
 
   fn run(&mut self, (dt, mut grid): Self::SystemData) {
     grid.nodes.par_iter_mut().for_each(|node| match node.boundary {
       Boundary::Friction { normal, mu } => {
-        let norm_vel = Vector3f::dot(&normal, &node.velocity_temp) * normal;
-        let tan_vel = node.velocity_temp - norm_vel;
-
-        // Make sure that we have velocity in tangent velocity direction
-        if tan_vel.magnitude() > std::f32::EPSILON {
-          // Calculate friction force magnitude
-          let normal_force_mag = -f32::min(Vector3f::dot(&normal, &node.force), 0.0);
-          let max_force_mag = node.mass * tan_vel.magnitude() / dt.get();
-          let fric_force_mag = f32::min(normal_force_mag * mu, max_force_mag);
-
-          // Friction force direction is the opposite of tangent velocity
-          let fric_force_dir = -tan_vel.normalize();
-
-          node.force += fric_force_dir * fric_force_mag;
+        node.force += Self::friction(normal, mu, node.mass, node.velocity_temp, node.force, dt.get());
+        for field in &mut node.fields {
+          field.force += Self::friction(normal, mu, field.mass, field.velocity_temp, field.force, dt.get());
         }
       }
       _ => {}
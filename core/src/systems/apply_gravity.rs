@@ -11,6 +11,9 @@ impl<'a> System<'a> for ApplyGravitySystem {
   fn run(&mut self, (gravity, mut grid): Self::SystemData) {
     grid.nodes.par_iter_mut().for_each(|node| {
       node.force += gravity.get() * node.mass;
+      for field in &mut node.fields {
+        field.force += gravity.get() * field.mass;
+      }
     })
   }
 }
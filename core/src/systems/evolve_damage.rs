@@ -0,0 +1,35 @@
+use specs::prelude::*;
+
+use crate::components::*;
+use crate::utils::*;
+
+/// Update the damage of particles from their new elastic deformation. Fractured particles
+/// keep only the compressive part of their elastic volume change, moving the rest into
+/// $F_P$, so that broken pieces separate freely but do not interpenetrate.
+pub struct EvolveDamageSystem;
+
+impl<'a> System<'a> for EvolveDamageSystem {
+  type SystemData = (
+    Entities<'a>,
+    WriteStorage<'a, ParticleDeformation>,
+    ReadStorage<'a, ParticleConstitutiveModel>,
+    WriteStorage<'a, ParticleDamage>,
+  );
+
+  fn run(&mut self, (entities, mut deformations, models, mut damages): Self::SystemData) {
+    (&entities, &mut deformations, &mut damages)
+      .par_join()
+      .for_each(|(entity, def, damage)| {
+        let model = models.get(entity).map_or(
+          &FixedCorotated as &dyn ConstitutiveModel,
+          ParticleConstitutiveModel::get,
+        );
+        let value = damage.criterion.evaluate(def, model);
+        damage.update(value);
+
+        if damage.is_fractured() {
+          def.remove_elastic_shear(1.0);
+        }
+      });
+  }
+}
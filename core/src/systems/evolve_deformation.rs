@@ -6,45 +6,110 @@ use crate::utils::*;
 
 pub struct EvolveDeformationSystem;
 
+impl EvolveDeformationSystem {
+  /// Evolve the deformation gradients of a particle given its velocity gradient. The elastic
+  /// singular values are projected by the plasticity model of the particle, or clamped
+  /// within `theta_c` and `theta_s` if there is none, and then relaxed for viscoelastic particles.
+  fn evolve(
+    def: &mut ParticleDeformation,
+    plasticity: Option<&mut ParticlePlasticity>,
+    viscoelasticity: Option<&ParticleViscoelasticity>,
+    grad_vp: Matrix3f,
+    dt: Float,
+  ) {
+    // First compute $\hat{F_{E_p}^{n + 1}}$ and $F_p^{n + 1}$
+    let temp_f_e = (Matrix3f::identity() + dt * grad_vp) * def.f_elastic;
+    let new_f = temp_f_e * def.f_plastic;
+
+    // Do SVD on temp_f_e and project the singular values
+    let (u, sigma_hat, v) = Math::svd3(&temp_f_e);
+    let snow = SnowPlasticity::new(def.theta_c, def.theta_s);
+    let (model, mut state) = match &plasticity {
+      Some(plasticity) => (plasticity.get(), plasticity.state),
+      None => (&snow as &dyn PlasticityModel, 0.0),
+    };
+    let (mu, lambda) = def.lame_parameters();
+    let sigma = model.project(&sigma_hat, mu, lambda, &mut state);
+    let sigma = viscoelasticity.map_or(sigma, |visco| visco.relax(&sigma, dt));
+    let sigma_inv = Vector3f::new(1.0 / sigma.x, 1.0 / sigma.y, 1.0 / sigma.z);
+
+    // New $F_{E_p}$
+    let new_f_e = u * Matrix3f::from_diagonal(&sigma) * v.transpose();
+    let new_f_p = v * Matrix3f::from_diagonal(&sigma_inv) * u.transpose() * new_f;
+
+    // Guard against degenerate plastic deformation (e.g. from an inverted
+    // element); treat the step as purely elastic instead
+    let j_p = new_f_p.determinant();
+    if j_p.is_finite() && j_p > 0.0 {
+      def.f_elastic = new_f_e;
+      def.f_plastic = new_f_p;
+      if let Some(plasticity) = plasticity {
+        plasticity.state = state;
+      }
+    } else {
+      def.f_elastic = temp_f_e;
+    }
+  }
+}
+
 impl<'a> System<'a> for EvolveDeformationSystem {
   type SystemData = (
+    Entities<'a>,
     Read<'a, DeltaTime>,
+    Read<'a, Pipeline>,
     Read<'a, Grid>,
     ReadStorage<'a, ParticlePosition>,
+    ReadStorage<'a, ParticleAffineVelocity>,
+    ReadStorage<'a, ParticleVolume>,
     WriteStorage<'a, ParticleDeformation>,
+    WriteStorage<'a, ParticlePlasticity>,
+    ReadStorage<'a, ParticleViscoelasticity>,
+    ReadStorage<'a, ParticleBody>,
   );
 
-  fn run(&mut self, (dt, grid, positions, mut deformations): Self::SystemData) {
-    (&positions, &mut deformations).par_join().for_each(|(position, def)| {
-      // First compute gradient v_p
-      let mut grad_vp = Matrix3f::zeros();
-      for (node_index, _, grad_w) in grid.neighbor_weights(position.get()) {
-        let node = grid.get_node(node_index);
-        grad_vp += node.velocity * grad_w.transpose();
+  fn run(
+    &mut self,
+    (
+      entities,
+      dt,
+      pipeline,
+      grid,
+      positions,
+      affines,
+      volumes,
+      mut deformations,
+      mut plasticities,
+      viscoelasticities,
+      bodies,
+    ): Self::SystemData,
+  ) {
+    let velocity_gradient = |entity, def: &ParticleDeformation| match *pipeline {
+      Pipeline::Standard => {
+        let position = positions.get(entity).map_or(Vector3f::zeros(), ParticlePosition::get);
+        let volume = volumes.get(entity).map_or(0.0, ParticleVolume::get);
+        let f = def.deformation_gradient();
+        let body = grid.field_of(bodies.get(entity).map(ParticleBody::get));
+        grid.velocity_gradient(grid.particle_weights(position, volume, &f), body)
       }
 
-      // Then compute $\hat{F_{E_p}^{n + 1}}$ and $F_p^{n + 1}$
-      let temp_f_e = (Matrix3f::identity() + dt.get() * grad_vp) * def.f_elastic;
-      let new_f = temp_f_e * def.f_plastic;
-
-      // Do SVD on temp_f_e
-      let svd = temp_f_e.svd(true, true);
-      match (svd.u, svd.v_t) {
-        (Some(u), Some(v_t)) => {
-          // Clamp out values in sigma
-          let sigma_hat = svd.singular_values;
-          let sigma = Math::clamp_vec(&sigma_hat, 1.0 - def.theta_c, 1.0 + def.theta_s);
-          let sigma_inv = Vector3f::new(1.0 / sigma.x, 1.0 / sigma.y, 1.0 / sigma.z);
-
-          // New $F_{E_p}$
-          let new_f_e = u * Matrix3f::from_diagonal(&sigma) * v_t;
-          let new_f_p = v_t.transpose() * Matrix3f::from_diagonal(&sigma_inv) * u.transpose() * new_f;
-
-          def.f_elastic = new_f_e;
-          def.f_plastic = new_f_p;
-        }
-        _ => panic!("Cannot decompose svd"),
-      }
-    })
+      // MLS-MPM uses the affine velocity as the velocity gradient
+      Pipeline::MlsMpm => affines
+        .get(entity)
+        .map_or(Matrix3f::zeros(), ParticleAffineVelocity::get),
+    };
+
+    (&entities, &mut deformations, !&plasticities)
+      .par_join()
+      .for_each(|(entity, def, _)| {
+        let grad_vp = velocity_gradient(entity, def);
+        Self::evolve(def, None, viscoelasticities.get(entity), grad_vp, dt.get());
+      });
+
+    (&entities, &mut deformations, &mut plasticities)
+      .par_join()
+      .for_each(|(entity, def, plasticity)| {
+        let grad_vp = velocity_gradient(entity, def);
+        Self::evolve(def, Some(plasticity), viscoelasticities.get(entity), grad_vp, dt.get());
+      });
   }
 }
@@ -0,0 +1,48 @@
+use specs::prelude::*;
+
+use crate::components::*;
+use crate::resources::*;
+use crate::utils::*;
+
+/// Evolve the volume ratio of fluid particles, $J^{n + 1} = \det(I + \Delta t \nabla v_p) J^n$
+pub struct EvolveFluidSystem;
+
+impl<'a> System<'a> for EvolveFluidSystem {
+  type SystemData = (
+    Entities<'a>,
+    Read<'a, DeltaTime>,
+    Read<'a, Pipeline>,
+    Read<'a, Grid>,
+    ReadStorage<'a, ParticlePosition>,
+    ReadStorage<'a, ParticleAffineVelocity>,
+    ReadStorage<'a, ParticleVolume>,
+    WriteStorage<'a, ParticleFluid>,
+    ReadStorage<'a, ParticleBody>,
+  );
+
+  fn run(&mut self, (entities, dt, pipeline, grid, positions, affines, volumes, mut fluids, bodies): Self::SystemData) {
+    (&entities, &positions, &mut fluids)
+      .par_join()
+      .for_each(|(entity, position, fluid)| {
+        let grad_vp = match *pipeline {
+          Pipeline::Standard => {
+            let volume = volumes.get(entity).map_or(0.0, ParticleVolume::get);
+            let f = fluid.deformation_gradient();
+            let body = grid.field_of(bodies.get(entity).map(ParticleBody::get));
+            grid.velocity_gradient(grid.particle_weights(position.get(), volume, &f), body)
+          }
+
+          // MLS-MPM uses the affine velocity as the velocity gradient
+          Pipeline::MlsMpm => affines
+            .get(entity)
+            .map_or(Matrix3f::zeros(), ParticleAffineVelocity::get),
+        };
+
+        // Guard against inverted volume
+        let j = (Matrix3f::identity() + dt.get() * grad_vp).determinant() * fluid.j;
+        if j.is_finite() && j > 0.0 {
+          fluid.j = j;
+        }
+      });
+  }
+}
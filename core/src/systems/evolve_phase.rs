@@ -0,0 +1,31 @@
+use specs::prelude::*;
+
+use crate::components::*;
+use crate::utils::*;
+
+/// Keep the temperature of particles with `ParticleTemperature` and `ParticleDeformation` up to
+/// date for their Lame parameters, and melt and solidify the ones with `ParticleMelting`.
+/// Fully melted particles do not keep any elastic shear.
+pub struct EvolvePhaseSystem;
+
+impl<'a> System<'a> for EvolvePhaseSystem {
+  type SystemData = (
+    WriteStorage<'a, ParticleTemperature>,
+    WriteStorage<'a, ParticleMelting>,
+    WriteStorage<'a, ParticleDeformation>,
+  );
+
+  fn run(&mut self, (mut temperatures, mut meltings, mut deformations): Self::SystemData) {
+    for (temperature, melting, def) in (&mut temperatures, (&mut meltings).maybe(), &mut deformations).join() {
+      if let Some(melting) = melting {
+        let new_temperature = melting.exchange(temperature.get(), temperature.heat_capacity);
+        temperature.set(new_temperature);
+        def.liquid_fraction = melting.liquid_fraction(new_temperature);
+        if def.liquid_fraction >= 1.0 {
+          def.remove_elastic_shear(Float::INFINITY);
+        }
+      }
+      def.temperature = temperature.get();
+    }
+  }
+}
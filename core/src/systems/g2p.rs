@@ -6,36 +6,134 @@ use crate::utils::*;
 
 pub struct G2PSystem;
 
+impl G2PSystem {
+  /// Gather the PIC velocity, the FLIP velocity and the APIC affine velocity of a
+  /// particle from the grid. The affine velocity is $C_p = B_p D_p^{-1}$ with
+  /// $B_p = \sum_i w_{ip} v_i (x_i - x_p)^T$, or $C_p = \sum_i v_i \nabla w_{ip}^T$ when
+  /// the kernel has no constant $D_p$. Uses the velocity field of `body` if any
+  fn gather(
+    grid: &Grid,
+    weights: WeightIterator,
+    body: Option<usize>,
+    position: Vector3f,
+    velocity: Vector3f,
+  ) -> (Vector3f, Vector3f, Matrix3f) {
+    let inv_inertia = grid.apic_inverse_inertia();
+    let mut vpic = Vector3f::zeros();
+    let mut vflip = velocity;
+    let mut c = Matrix3f::zeros();
+    for (node_index, weight, grad_w) in weights {
+      let (velocity_temp, velocity) = grid.get_node(node_index).velocities(body);
+      vpic += weight * velocity;
+      vflip += weight * (velocity - velocity_temp);
+      c += match inv_inertia {
+        Some(d_inv) => weight * d_inv * velocity * (grid.node_position(node_index) - position).transpose(),
+        None => velocity * grad_w.transpose(),
+      };
+    }
+    (vpic, vflip, c)
+  }
+}
+
 impl<'a> System<'a> for G2PSystem {
   type SystemData = (
+    Entities<'a>,
     Read<'a, DeltaTime>,
+    Read<'a, TransferScheme>,
+    Read<'a, FlipRatio>,
     Read<'a, Grid>,
     WriteStorage<'a, ParticleVelocity>,
     WriteStorage<'a, ParticlePosition>,
+    WriteStorage<'a, ParticleAffineVelocity>,
+    ReadStorage<'a, ParticleFlipRatio>,
+    ReadStorage<'a, ParticleVolume>,
+    ReadStorage<'a, ParticleDeformation>,
+    ReadStorage<'a, ParticleBody>,
   );
 
-  fn run(&mut self, (dt, grid, mut velocities, mut positions): Self::SystemData) {
-    (&mut velocities, &mut positions)
+  fn run(
+    &mut self,
+    (
+      entities,
+      dt,
+      scheme,
+      flip_ratio,
+      grid,
+      mut velocities,
+      mut positions,
+      mut affines,
+      flip_ratios,
+      volumes,
+      deformations,
+      bodies,
+    ): Self::SystemData,
+  ) {
+    let scheme = *scheme;
+
+    // The particle's own flip ratio takes precedence over the world's
+    let ratio_of = |entity| flip_ratios.get(entity).map_or(flip_ratio.get(), ParticleFlipRatio::get);
+
+    // The weights of the particle taking its domain into account
+    let weights_of = |entity, position: Vector3f| {
+      let volume = volumes.get(entity).map_or(0.0, ParticleVolume::get);
+      let f = deformations
+        .get(entity)
+        .map_or(Matrix3f::identity(), ParticleDeformation::deformation_gradient);
+      grid.particle_weights(position, volume, &f)
+    };
+
+    // The velocity field used by the particle
+    let body_of = |entity| grid.field_of(bodies.get(entity).map(ParticleBody::get));
+
+    // Particles carrying an affine velocity
+    (&entities, &mut velocities, &mut positions, &mut affines)
       .par_join()
-      .for_each(|(velocity, position)| {
-        // Initialize velocities
-        let mut vpic = Vector3f::zeros();
-        let mut vflip = velocity.get();
-
-        // First calculate the new velocity of particle
-        for (node_index, weight, _) in grid.neighbor_weights(position.get()) {
-          let node = grid.get_node(node_index);
-          vpic += weight * node.velocity;
-          vflip += weight * (node.velocity - node.velocity_temp);
-        }
+      .for_each(|(entity, velocity, position, affine)| {
+        let (vpic, vflip, c) = Self::gather(
+          &grid,
+          weights_of(entity, position.get()),
+          body_of(entity),
+          position.get(),
+          velocity.get(),
+        );
+
+        // Get the new velocity according to the transfer scheme
+        let new_vel = match scheme {
+          TransferScheme::PicFlip => {
+            let ratio = ratio_of(entity);
+            (1.0 - ratio) * vpic + ratio * vflip
+          }
+          TransferScheme::Apic => {
+            affine.set(c);
+            vpic
+          }
+        };
 
         // Then use forward computation to get new position
-        let new_vel = 0.05 * vpic + 0.95 * vflip;
-        let new_pos = position.get() + vpic * dt.get();
+        velocity.set(new_vel);
+        position.set(position.get() + vpic * dt.get());
+      });
 
-        // Set the velocity and position
+    // Particles without affine velocity fall back to plain PIC in APIC mode
+    (&entities, &mut velocities, &mut positions, !&affines)
+      .par_join()
+      .for_each(|(entity, velocity, position, _)| {
+        let (vpic, vflip, _) = Self::gather(
+          &grid,
+          weights_of(entity, position.get()),
+          body_of(entity),
+          position.get(),
+          velocity.get(),
+        );
+        let new_vel = match scheme {
+          TransferScheme::PicFlip => {
+            let ratio = ratio_of(entity);
+            (1.0 - ratio) * vpic + ratio * vflip
+          }
+          TransferScheme::Apic => vpic,
+        };
         velocity.set(new_vel);
-        position.set(new_pos);
-      })
+        position.set(position.get() + vpic * dt.get());
+      });
   }
 }
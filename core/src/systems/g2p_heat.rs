@@ -0,0 +1,36 @@
+use specs::prelude::*;
+
+use crate::components::*;
+use crate::resources::*;
+use crate::utils::*;
+
+/// Grid to Particle transfer of heat. Particles receive the change of temperature of the
+/// nodes around them, so that they do not diffuse heat when the grid does not, without
+/// going beyond the range of temperatures around them.
+pub struct G2PHeatSystem;
+
+impl<'a> System<'a> for G2PHeatSystem {
+  type SystemData = (
+    Read<'a, Grid>,
+    ReadStorage<'a, ParticlePosition>,
+    WriteStorage<'a, ParticleTemperature>,
+  );
+
+  fn run(&mut self, (grid, positions, mut temperatures): Self::SystemData) {
+    (&positions, &mut temperatures)
+      .par_join()
+      .for_each(|(position, temperature)| {
+        let (mut change, mut min, mut max) = (0.0, Float::INFINITY, Float::NEG_INFINITY);
+        for (node_index, weight, _) in grid.neighbor_weights(position.get()) {
+          let node = grid.get_node(node_index);
+          change += weight * (node.temperature - node.temperature_temp);
+          min = min.min(node.temperature);
+          max = max.max(node.temperature);
+        }
+
+        // Do not overshoot the temperatures of the nodes around
+        let (low, high) = (min.min(temperature.get()), max.max(temperature.get()));
+        temperature.set((temperature.get() + change).max(low).min(high));
+      });
+  }
+}
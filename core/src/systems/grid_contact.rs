@@ -0,0 +1,59 @@
+use rayon::prelude::*;
+use specs::prelude::*;
+
+use crate::resources::*;
+use crate::utils::*;
+
+/// Contact between the velocity fields of different bodies, following Bardenhagen et al.
+/// 2000, "The material-point method for granular materials". At nodes shared by several
+/// bodies, each body is compared against the center of mass velocity $v_{cm}$ of the node.
+/// A body approaching the others along its outward normal $n$ has its normal relative
+/// velocity removed, and its tangential relative velocity reduced by Coulomb friction.
+/// Bodies moving apart keep their own velocities.
+pub struct GridContactSystem;
+
+impl GridContactSystem {
+  /// The velocity of a body after contact with the other bodies at a node
+  fn resolve(velocity: Vector3f, velocity_cm: Vector3f, mass_gradient: Vector3f, mu: Float) -> Vector3f {
+    let grad_norm = mass_gradient.norm();
+    if grad_norm <= 0.0 {
+      return velocity;
+    }
+    let normal = -mass_gradient / grad_norm;
+
+    // Only correct bodies approaching each other
+    let relative = velocity - velocity_cm;
+    let approach = relative.dot(&normal);
+    if approach <= 0.0 {
+      return velocity;
+    }
+
+    // Stick when the friction is large enough, otherwise slide
+    let tangent = relative - approach * normal;
+    let tangent_norm = tangent.norm();
+    if tangent_norm <= mu * approach {
+      velocity_cm
+    } else {
+      velocity_cm + tangent * (1.0 - mu * approach / tangent_norm)
+    }
+  }
+}
+
+impl<'a> System<'a> for GridContactSystem {
+  type SystemData = Write<'a, Grid>;
+
+  fn run(&mut self, mut grid: Self::SystemData) {
+    let mu = match grid.contact_friction {
+      Some(mu) => mu,
+      None => return,
+    };
+    grid.nodes.par_iter_mut().for_each(|node| {
+      if node.fields.len() > 1 && node.mass != 0.0 {
+        let velocity_cm = node.velocity;
+        for field in &mut node.fields {
+          field.velocity = Self::resolve(field.velocity, velocity_cm, field.mass_gradient, mu);
+        }
+      }
+    })
+  }
+}
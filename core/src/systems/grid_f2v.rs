@@ -13,6 +13,11 @@ impl<'a> System<'a> for GridF2VSystem {
       if node.mass != 0.0 {
         node.velocity = node.velocity_temp + node.force / node.mass * dt.get();
       }
+      for field in &mut node.fields {
+        if field.mass != 0.0 {
+          field.velocity = field.velocity_temp + field.force / field.mass * dt.get();
+        }
+      }
     })
   }
 }
@@ -0,0 +1,80 @@
+use rayon::prelude::*;
+use specs::prelude::*;
+
+use crate::components::*;
+use crate::resources::*;
+use crate::utils::*;
+
+/// Heat diffusion on the grid. Each node exchanges heat with its 6 direct neighbors through
+/// the faces between them, with a flux of $k \Delta x (T_j - T_i)$. Neighbors without heat
+/// capacity are insulating, unless they have a boundary temperature. Each step does a single
+/// Jacobi sweep of the backward Euler update, using the temperatures of the neighbors from the
+/// previous step:
+///
+/// $$T_i^{n + 1} = \frac{C_i T_i^n + \Delta t \Delta x \sum_j k_{ij} T_j^n}{C_i + \Delta t \Delta x \sum_j k_{ij}}$$
+///
+/// The new temperature is a weighted average of the old ones, so it stays stable for large
+/// time steps, but heat only travels one node per step. It reaches the same steady state as
+/// the backward Euler solve. Nodes with a boundary temperature are fixed to it.
+pub struct GridHeatDiffusionSystem;
+
+impl<'a> System<'a> for GridHeatDiffusionSystem {
+  type SystemData = (
+    Read<'a, DeltaTime>,
+    Write<'a, Grid>,
+    ReadStorage<'a, ParticleTemperature>,
+  );
+
+  fn run(&mut self, (dt, mut grid, temperatures): Self::SystemData) {
+    // Skip the whole grid if nothing carries a temperature
+    if temperatures.join().next().is_none() {
+      return;
+    }
+
+    let factor = dt.get() * grid.dx;
+    let offsets = [
+      Vector3i::new(-1, 0, 0),
+      Vector3i::new(1, 0, 0),
+      Vector3i::new(0, -1, 0),
+      Vector3i::new(0, 1, 0),
+      Vector3i::new(0, 0, -1),
+      Vector3i::new(0, 0, 1),
+    ];
+    let new_temperatures: Vec<Float> = (0..grid.nodes.len())
+      .into_par_iter()
+      .map(|raw_index| {
+        let (node, node_index) = (&grid.nodes[raw_index], grid.node_index(raw_index));
+        if let Some(temperature) = node.boundary_temperature {
+          return temperature;
+        }
+        if node.heat_capacity <= 0.0 {
+          return node.temperature_temp;
+        }
+        let (mut num, mut denom) = (node.heat_capacity * node.temperature_temp, node.heat_capacity);
+        for offset in offsets.iter() {
+          let neighbor_index = Vector3i::new(node_index.x as i32, node_index.y as i32, node_index.z as i32) + offset;
+          // Neighbors with a fixed temperature count even when their block is not active
+          let (conductivity, temperature) = match grid.boundary_temperature(neighbor_index) {
+            Some(temperature) => (node.conductivity, temperature),
+            None => match grid.get_node_checked(neighbor_index) {
+              Some(neighbor) if neighbor.heat_capacity > 0.0 => (
+                0.5 * (node.conductivity + neighbor.conductivity),
+                neighbor.temperature_temp,
+              ),
+              _ => continue,
+            },
+          };
+          num += factor * conductivity * temperature;
+          denom += factor * conductivity;
+        }
+        num / denom
+      })
+      .collect();
+
+    grid
+      .nodes
+      .par_iter_mut()
+      .zip(new_temperatures)
+      .for_each(|(node, temperature)| node.temperature = temperature);
+  }
+}
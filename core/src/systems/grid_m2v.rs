@@ -17,6 +17,11 @@ impl<'a> System<'a> for GridM2VSystem {
       } else {
         node.velocity_temp = node.momentum / node.mass;
       }
+      for field in &mut node.fields {
+        if field.mass != 0.0 {
+          field.velocity_temp = field.momentum / field.mass;
+        }
+      }
     })
   }
 }
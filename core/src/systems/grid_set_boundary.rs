@@ -2,7 +2,6 @@ use rayon::prelude::*;
 use specs::prelude::*;
 
 use crate::resources::*;
-use crate::utils::*;
 
 pub struct GridSetBoundarySystem;
 
@@ -10,13 +9,19 @@ impl<'a> System<'a> for GridSetBoundarySystem {
   type SystemData = Write<'a, Grid>;
 
   fn run(&mut self, mut grid: Self::SystemData) {
-    grid.nodes.par_iter_mut().for_each(|node| match node.boundary {
-      Boundary::None => {}
-      Boundary::Sticky => {
-        node.velocity = Vector3f::zeros();
+    let planar = grid.planar;
+    grid.nodes.par_iter_mut().for_each(|node| {
+      node.velocity = node.boundary.project(node.velocity);
+      for field in &mut node.fields {
+        field.velocity = node.boundary.project(field.velocity);
       }
-      Boundary::Sliding { normal } | Boundary::Friction { normal, .. } => {
-        node.velocity -= f32::min(Vector3f::dot(&node.velocity, &normal), 0.0) * normal;
+
+      // Nothing moves out of the plane in 2D
+      if planar {
+        node.velocity.z = 0.0;
+        for field in &mut node.fields {
+          field.velocity.z = 0.0;
+        }
       }
     })
   }
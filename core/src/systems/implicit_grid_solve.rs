@@ -0,0 +1,336 @@
+use rayon::prelude::*;
+use specs::prelude::*;
+
+use crate::components::*;
+use crate::resources::*;
+use crate::utils::*;
+
+/// Backward Euler solve of the grid velocities, run after the explicit forces are
+/// applied by `GridF2VSystem`. With $v^*$ the explicit velocities, the new velocities
+/// minimize the incremental potential
+///
+/// $$E(v) = \sum_i \frac{1}{2} m_i \|v_i - v_i^*\|^2 + \sum_p V_p^0 \Psi(\hat{F}_{E_p}(v))$$
+///
+/// where $\hat{F}_{E_p}(v) = (I + \Delta t \sum_i v_i \nabla w_{ip}^T) F_{E_p}$. Each Newton
+/// iteration solves $H \delta v = -\nabla E$ with a matrix-free conjugate gradient, followed
+/// by a backtracking line search on $E$. The boundaries of the nodes are taken into account
+/// as constraints.
+pub struct ImplicitGridSolveSystem;
+
+/// A deformable particle taking part in the solve
+struct ImplicitParticle<'a> {
+  volume: Float,
+  def: &'a ParticleDeformation,
+  model: &'a dyn ConstitutiveModel,
+
+  /// Scale of the stress, e.g. from damage
+  factor: Float,
+
+  /// The raw node index and weight gradient of each neighbor node
+  weights: Vec<(usize, Vector3f)>,
+}
+
+impl<'a> ImplicitParticle<'a> {
+  /// $\hat{F}_{E_p}$ given the node velocities
+  fn f_hat(&self, v: &[Vector3f], dt: Float) -> Matrix3f {
+    let mut grad_v = Matrix3f::zeros();
+    for (i, grad_w) in &self.weights {
+      grad_v += v[*i] * grad_w.transpose();
+    }
+    (Matrix3f::identity() + dt * grad_v) * self.def.f_elastic
+  }
+
+  /// The stress differential $\frac{\partial P}{\partial F}$ at `f_hat`, as a matrix acting on
+  /// the column-major entries of $dF$. Computed once per Newton iteration so that the Hessian
+  /// products do not need to evaluate the constitutive model
+  fn stiffness(&self, f_hat: &Matrix3f) -> Stiffness {
+    let mut k = Stiffness::zeros();
+    for c in 0..9 {
+      let mut df = Matrix3f::zeros();
+      df[c] = 1.0;
+      let dp = self.def.first_piola_kirchhoff_differential(self.model, f_hat, &df);
+      k.column_mut(c).copy_from_slice(dp.as_slice());
+    }
+    k
+  }
+}
+
+type Stiffness = na::MatrixN<Float, na::U9>;
+
+impl ImplicitGridSolveSystem {
+  fn dot(a: &[Vector3f], b: &[Vector3f]) -> Float {
+    a.par_iter().zip(b.par_iter()).map(|(x, y)| x.dot(y)).sum()
+  }
+
+  /// The incremental potential $E(v)$
+  fn energy(particles: &[ImplicitParticle], masses: &[Float], v: &[Vector3f], v_star: &[Vector3f], dt: Float) -> Float {
+    let kinetic: Float = (masses, v, v_star)
+      .into_par_iter()
+      .map(|(m, v, v_star)| 0.5 * *m * (v - v_star).norm_squared())
+      .sum();
+    let elastic: Float = particles
+      .par_iter()
+      .map(|particle| {
+        particle.factor * particle.volume * particle.def.energy_density(particle.model, &particle.f_hat(v, dt))
+      })
+      .sum();
+    kinetic + elastic
+  }
+
+  /// The gradient $\nabla E(v)$, projected by the constraints of the nodes
+  fn gradient(
+    particles: &[ImplicitParticle],
+    masses: &[Float],
+    constraints: &[Matrix3f],
+    v: &[Vector3f],
+    v_star: &[Vector3f],
+    dt: Float,
+  ) -> Vec<Vector3f> {
+    let mut g: Vec<Vector3f> = (masses, v, v_star)
+      .into_par_iter()
+      .map(|(m, v, v_star)| *m * (v - v_star))
+      .collect();
+    for particle in particles {
+      let stress = particle.factor
+        * particle.volume
+        * particle
+          .def
+          .first_piola_kirchhoff(particle.model, &particle.f_hat(v, dt))
+        * particle.def.f_elastic.transpose();
+      for (i, grad_w) in &particle.weights {
+        g[*i] += dt * stress * grad_w;
+      }
+    }
+    Self::project(&mut g, constraints);
+    g
+  }
+
+  /// The Hessian product $H d$ given the stiffness of each particle, projected by the constraints
+  /// of the nodes
+  fn hessian_product(
+    particles: &[ImplicitParticle],
+    stiffnesses: &[Stiffness],
+    masses: &[Float],
+    constraints: &[Matrix3f],
+    d: &[Vector3f],
+    dt: Float,
+  ) -> Vec<Vector3f> {
+    let mut hd: Vec<Vector3f> = (masses, d).into_par_iter().map(|(m, d)| *m * d).collect();
+    let d_stresses: Vec<Matrix3f> = (particles, stiffnesses)
+      .into_par_iter()
+      .map(|(particle, stiffness)| {
+        let mut grad_d = Matrix3f::zeros();
+        for (i, grad_w) in &particle.weights {
+          grad_d += d[*i] * grad_w.transpose();
+        }
+        let df = dt * grad_d * particle.def.f_elastic;
+        let dp = stiffness * na::VectorN::<Float, na::U9>::from_column_slice(df.as_slice());
+        let dp = Matrix3f::from_column_slice(dp.as_slice());
+        particle.factor * particle.volume * dp * particle.def.f_elastic.transpose()
+      })
+      .collect();
+    for (particle, d_stress) in particles.iter().zip(d_stresses.iter()) {
+      for (i, grad_w) in &particle.weights {
+        hd[*i] += dt * d_stress * grad_w;
+      }
+    }
+    Self::project(&mut hd, constraints);
+    hd
+  }
+
+  /// The diagonal of the Hessian, used as the preconditioner of the conjugate gradient. It is
+  /// bounded below by the node masses to stay positive
+  fn hessian_diagonal(
+    particles: &[ImplicitParticle],
+    stiffnesses: &[Stiffness],
+    masses: &[Float],
+    dt: Float,
+  ) -> Vec<Vector3f> {
+    let mut diag = vec![Vector3f::zeros(); masses.len()];
+    for (particle, stiffness) in particles.iter().zip(stiffnesses.iter()) {
+      for (i, grad_w) in &particle.weights {
+        // $dF = \Delta t e_a (F_E^T \nabla w_{ip})^T$ for a unit velocity along axis $a$
+        let q = particle.def.f_elastic.transpose() * grad_w;
+        for (a, d) in diag[*i].iter_mut().enumerate() {
+          let mut df = Matrix3f::zeros();
+          df.set_row(a, &(dt * q).transpose());
+          let dp = stiffness * na::VectorN::<Float, na::U9>::from_column_slice(df.as_slice());
+          let dp = Matrix3f::from_column_slice(dp.as_slice());
+          *d += dt * particle.factor * particle.volume * (dp * q)[a];
+        }
+      }
+    }
+    diag
+      .par_iter()
+      .zip(masses.par_iter())
+      .map(|(d, m)| d.map(|d| Float::max(d + m, *m)))
+      .collect()
+  }
+
+  /// Apply the Jacobi preconditioner to a vector on the nodes
+  fn precondition(r: &[Vector3f], diag: &[Vector3f], constraints: &[Matrix3f]) -> Vec<Vector3f> {
+    (r, diag, constraints)
+      .into_par_iter()
+      .map(|(r, d, constraint)| constraint * r.zip_map(d, |r, d| if d > 0.0 { r / d } else { 0.0 }))
+      .collect()
+  }
+
+  /// Remove the constrained components of a vector on the nodes
+  fn project(v: &mut [Vector3f], constraints: &[Matrix3f]) {
+    v.par_iter_mut()
+      .zip(constraints.par_iter())
+      .for_each(|(v, constraint)| *v = constraint * *v);
+  }
+}
+
+impl<'a> System<'a> for ImplicitGridSolveSystem {
+  type SystemData = (
+    Read<'a, DeltaTime>,
+    Read<'a, Integrator>,
+    Write<'a, Grid>,
+    ReadStorage<'a, ParticlePosition>,
+    ReadStorage<'a, ParticleVolume>,
+    ReadStorage<'a, ParticleDeformation>,
+    ReadStorage<'a, ParticleConstitutiveModel>,
+    ReadStorage<'a, ParticleDamage>,
+  );
+
+  fn run(&mut self, (dt, integrator, mut grid, positions, volumes, deformations, models, damages): Self::SystemData) {
+    let (newton_iterations, cg_iterations, tolerance) = match *integrator {
+      Integrator::Implicit {
+        newton_iterations,
+        cg_iterations,
+        tolerance,
+      } => (newton_iterations, cg_iterations, tolerance),
+      Integrator::Explicit => return,
+    };
+    let dt = dt.get();
+
+    // Gather the particles along with their neighbor nodes
+    let particles: Vec<ImplicitParticle> = (&positions, &volumes, &deformations, models.maybe(), damages.maybe())
+      .join()
+      .map(|(position, volume, def, model, damage)| {
+        let f = def.deformation_gradient();
+        let weights = grid
+          .particle_weights(position.get(), volume.get(), &f)
+          .filter_map(|(node_index, _, grad_w)| grid.raw_index(node_index).map(|i| (i, grad_w)))
+          .collect();
+        ImplicitParticle {
+          volume: volume.get(),
+          def,
+          model: model.map_or(
+            &FixedCorotated as &dyn ConstitutiveModel,
+            ParticleConstitutiveModel::get,
+          ),
+          factor: damage.map_or(1.0, |damage| damage.stress_factor(&def.f_elastic)),
+          weights,
+        }
+      })
+      .collect();
+    if particles.is_empty() {
+      return;
+    }
+
+    // The explicit velocities with the boundaries applied are the initial guess. Empty and
+    // sticky nodes are fixed, sliding nodes in contact can only move along the wall, and
+    // nodes of a 2D grid can only move in the plane
+    let masses: Vec<Float> = grid.nodes.iter().map(|node| node.mass).collect();
+    let plane = grid.project_planar(Vector3f::repeat(1.0));
+    let constraints: Vec<Matrix3f> = grid
+      .nodes
+      .par_iter()
+      .map(|node| match node.boundary {
+        _ if node.mass == 0.0 => Matrix3f::zeros(),
+        Boundary::Sticky => Matrix3f::zeros(),
+        Boundary::Sliding { normal } | Boundary::Friction { normal, .. } if node.velocity.dot(&normal) < 0.0 => {
+          Matrix3f::from_diagonal(&plane) - normal * normal.transpose()
+        }
+        _ => Matrix3f::from_diagonal(&plane),
+      })
+      .collect();
+    let v_star: Vec<Vector3f> = grid.nodes.iter().map(|node| node.velocity).collect();
+    let mut v: Vec<Vector3f> = grid
+      .nodes
+      .iter()
+      .map(|node| grid.project_planar(node.boundary.project(node.velocity)))
+      .collect();
+
+    // The residual is measured against the initial gradient and the explicit momentum
+    let mut g = Self::gradient(&particles, &masses, &constraints, &v, &v_star, dt);
+    let momentum: Vec<Vector3f> = (&masses, &v_star).into_par_iter().map(|(m, v)| *m * v).collect();
+    let scale = Float::max(Self::dot(&g, &g).sqrt(), Self::dot(&momentum, &momentum).sqrt());
+    for _ in 0..newton_iterations {
+      let g_norm = Self::dot(&g, &g).sqrt();
+      if g_norm <= tolerance * scale {
+        break;
+      }
+
+      // Solve $H \delta v = -\nabla E$ by preconditioned conjugate gradient, stopping at
+      // negative curvature
+      let stiffnesses: Vec<Stiffness> = particles
+        .par_iter()
+        .map(|particle| particle.stiffness(&particle.f_hat(&v, dt)))
+        .collect();
+      let diag = Self::hessian_diagonal(&particles, &stiffnesses, &masses, dt);
+      let mut delta = vec![Vector3f::zeros(); v.len()];
+      let mut r: Vec<Vector3f> = g.par_iter().map(|g| -g).collect();
+      let mut z = Self::precondition(&r, &diag, &constraints);
+      let mut p = z.clone();
+      let mut rz = Self::dot(&r, &z);
+      for _ in 0..cg_iterations {
+        if Self::dot(&r, &r).sqrt() <= tolerance * g_norm {
+          break;
+        }
+        let hp = Self::hessian_product(&particles, &stiffnesses, &masses, &constraints, &p, dt);
+        let php = Self::dot(&p, &hp);
+        if php <= 0.0 {
+          break;
+        }
+        let alpha = rz / php;
+        delta
+          .par_iter_mut()
+          .zip(p.par_iter())
+          .for_each(|(d, p)| *d += alpha * p);
+        r.par_iter_mut().zip(hp.par_iter()).for_each(|(r, hp)| *r -= alpha * hp);
+        z = Self::precondition(&r, &diag, &constraints);
+        let rz_new = Self::dot(&r, &z);
+        let beta = rz_new / rz;
+        p.par_iter_mut().zip(z.par_iter()).for_each(|(p, z)| *p = z + beta * *p);
+        rz = rz_new;
+      }
+      if Self::dot(&delta, &delta) == 0.0 {
+        // Negative curvature at the first iteration; descend along the gradient instead
+        delta = r;
+      }
+
+      // Backtracking line search on the incremental potential
+      let energy = Self::energy(&particles, &masses, &v, &v_star, dt);
+      let mut step = 1.0;
+      let accepted = loop {
+        let next: Vec<Vector3f> = v.par_iter().zip(delta.par_iter()).map(|(v, d)| v + step * d).collect();
+        let next_energy = Self::energy(&particles, &masses, &next, &v_star, dt);
+        if next_energy.is_finite() && next_energy <= energy {
+          break Some(next);
+        }
+        step *= 0.5;
+        if step < 1e-3 {
+          break None;
+        }
+      };
+      match accepted {
+        Some(next) => v = next,
+        None => break,
+      }
+      g = Self::gradient(&particles, &masses, &constraints, &v, &v_star, dt);
+    }
+
+    // Write back the velocities. Body fields get the same change as the node
+    grid.nodes.par_iter_mut().zip(v.par_iter()).for_each(|(node, v)| {
+      let dv = v - node.velocity;
+      node.velocity = *v;
+      for field in &mut node.fields {
+        field.velocity += dv;
+      }
+    });
+  }
+}
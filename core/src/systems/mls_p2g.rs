@@ -0,0 +1,117 @@
+use rayon::prelude::*;
+use specs::prelude::*;
+
+use crate::components::*;
+use crate::resources::*;
+use crate::utils::*;
+
+/// Particle to Grid transfer of MLS-MPM. Scatters mass and APIC momentum like
+/// `P2GSystem`, and additionally scatters the elastic force using the MLS
+/// approximation of the weight gradient, $\nabla w_{ip} \approx w_{ip} D_p^{-1} (x_i - x_p)$,
+/// so that no separate elasticity pass over the neighbors is needed. Kernels without a
+/// constant $D_p$ use the weight gradient itself.
+pub struct MlsP2GSystem;
+
+impl<'a> System<'a> for MlsP2GSystem {
+  type SystemData = (
+    Entities<'a>,
+    Write<'a, Grid>,
+    ReadStorage<'a, ParticleMass>,
+    ReadStorage<'a, ParticleVelocity>,
+    ReadStorage<'a, ParticlePosition>,
+    ReadStorage<'a, ParticleAffineVelocity>,
+    ReadStorage<'a, ParticleVolume>,
+    ReadStorage<'a, ParticleDeformation>,
+    ReadStorage<'a, ParticleConstitutiveModel>,
+    ReadStorage<'a, ParticleFluid>,
+    ReadStorage<'a, ParticleViscoelasticity>,
+    ReadStorage<'a, ParticleMelting>,
+    ReadStorage<'a, ParticleDamage>,
+    ReadStorage<'a, ParticleBody>,
+  );
+
+  fn run(
+    &mut self,
+    (
+      entities,
+      mut grid,
+      masses,
+      velocities,
+      positions,
+      affines,
+      volumes,
+      deformations,
+      models,
+      fluids,
+      viscoelasticities,
+      meltings,
+      damages,
+      bodies,
+    ): Self::SystemData,
+  ) {
+    let inv_inertia = grid.apic_inverse_inertia();
+    // The stresses are computed in parallel, then scattered with the mass and momentum
+    let particles: Vec<_> = (&entities, &masses, &velocities, &positions)
+      .par_join()
+      .map(|(entity, mass, velocity, position)| {
+        let (affine, volume, def) = (affines.get(entity), volumes.get(entity), deformations.get(entity));
+        let (model, fluid, visco) = (models.get(entity), fluids.get(entity), viscoelasticities.get(entity));
+        let (melting, damage) = (meltings.get(entity), damages.get(entity));
+        let body = grid.field_of(bodies.get(entity).map(ParticleBody::get));
+        let c = affine.map_or(Matrix3f::zeros(), ParticleAffineVelocity::get);
+
+        // Weights of the particle taking its domain into account
+        let f = match (def, fluid) {
+          (Some(def), _) => def.deformation_gradient(),
+          (None, Some(fluid)) => fluid.deformation_gradient(),
+          (None, None) => Matrix3f::identity(),
+        };
+
+        // $V_p^0 P(F_p) F_p^T$ (plus the viscous stress) for solids or $V_p^0 J_p \sigma_p$
+        // for fluids, zero for particles that do not deform
+        let stress = match (volume, def, fluid) {
+          (Some(volume), Some(def), _) => {
+            let model = model.map_or(
+              &FixedCorotated as &dyn ConstitutiveModel,
+              ParticleConstitutiveModel::get,
+            );
+            let factor = damage.map_or(1.0, |damage| damage.stress_factor(&def.f_elastic));
+            let p = factor * def.first_piola_kirchhoff(model, &def.f_elastic);
+            let mut sigma = visco.map_or(Matrix3f::zeros(), |visco| visco.viscous_stress(&c));
+            if let Some(melting) = melting {
+              sigma += melting.viscous_stress(def.liquid_fraction, &c);
+            }
+            let viscous = def.deformation_gradient().determinant() * sigma;
+            volume.get() * (p * def.f_elastic.transpose() + viscous)
+          }
+          (Some(volume), None, Some(fluid)) => volume.get() * fluid.j * fluid.cauchy_stress(&c),
+          _ => Matrix3f::zeros(),
+        };
+
+        ScatterParticle {
+          position: position.get(),
+          volume: volume.map_or(0.0, ParticleVolume::get),
+          f,
+          data: (mass.get(), velocity.get(), c, stress, body),
+        }
+      })
+      .collect();
+
+    grid.scatter(&particles, |node, node_position, weight, grad_w, p| {
+      let (mass, velocity, c, stress, body) = p.data;
+      let dpos = node_position - p.position;
+      let grad_m = mass * grad_w;
+      let grad_w = inv_inertia.map_or(grad_w, |d_inv| weight * d_inv * dpos);
+      let momentum = mass * weight * (velocity + c * dpos);
+      node.mass += mass * weight;
+      node.momentum += momentum;
+      if let Some(body) = body {
+        let field = node.field_mut(body);
+        field.mass += mass * weight;
+        field.momentum += momentum;
+        field.mass_gradient -= grad_m;
+      }
+      node.add_force(body, -stress * grad_w);
+    });
+  }
+}
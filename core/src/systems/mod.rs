@@ -1,23 +1,51 @@
+mod adapt_delta_time;
+mod advance_time;
 mod apply_elasticity;
+mod apply_fluid_stress;
 mod apply_friction;
 mod apply_gravity;
 mod clean_grid;
+mod evolve_damage;
 mod evolve_deformation;
+mod evolve_fluid;
+mod evolve_phase;
 mod g2p;
+mod g2p_heat;
+mod grid_contact;
 mod grid_f2v;
+mod grid_heat_diffusion;
 mod grid_m2v;
 mod grid_set_boundary;
+mod implicit_grid_solve;
+mod mls_p2g;
 mod p2g;
+mod p2g_heat;
+mod rigid_body_g2p;
+mod rigid_body_p2g;
 mod step_counter;
 
+pub use adapt_delta_time::*;
+pub use advance_time::*;
 pub use apply_elasticity::*;
+pub use apply_fluid_stress::*;
 pub use apply_friction::*;
 pub use apply_gravity::*;
 pub use clean_grid::*;
+pub use evolve_damage::*;
 pub use evolve_deformation::*;
+pub use evolve_fluid::*;
+pub use evolve_phase::*;
 pub use g2p::*;
+pub use g2p_heat::*;
+pub use grid_contact::*;
 pub use grid_f2v::*;
+pub use grid_heat_diffusion::*;
 pub use grid_m2v::*;
 pub use grid_set_boundary::*;
+pub use implicit_grid_solve::*;
+pub use mls_p2g::*;
 pub use p2g::*;
+pub use p2g_heat::*;
+pub use rigid_body_g2p::*;
+pub use rigid_body_p2g::*;
 pub use step_counter::*;
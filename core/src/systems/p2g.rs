@@ -1,25 +1,65 @@
+use rayon::prelude::*;
 use specs::prelude::*;
 
 use crate::components::*;
 use crate::resources::*;
+use crate::utils::*;
 
 pub struct P2GSystem;
 
 impl<'a> System<'a> for P2GSystem {
   type SystemData = (
+    Entities<'a>,
     Write<'a, Grid>,
     ReadStorage<'a, ParticleMass>,
     ReadStorage<'a, ParticleVelocity>,
     ReadStorage<'a, ParticlePosition>,
+    ReadStorage<'a, ParticleAffineVelocity>,
+    ReadStorage<'a, ParticleVolume>,
+    ReadStorage<'a, ParticleDeformation>,
+    ReadStorage<'a, ParticleBody>,
   );
 
-  fn run(&mut self, (mut grid, masses, velocities, positions): Self::SystemData) {
-    for (mass, velocity, position) in (&masses, &velocities, &positions).join() {
-      for (node_index, weight, _) in grid.neighbor_weights(position.get()) {
-        let node = grid.get_node_mut(node_index);
-        node.mass += mass.get() * weight;
-        node.momentum += mass.get() * velocity.get() * weight;
+  fn run(
+    &mut self,
+    (entities, mut grid, masses, velocities, positions, affines, volumes, deformations, bodies): Self::SystemData,
+  ) {
+    let particles: Vec<_> = (&entities, &masses, &velocities, &positions)
+      .par_join()
+      .map(|(entity, mass, velocity, position)| ScatterParticle {
+        position: position.get(),
+        volume: volumes.get(entity).map_or(0.0, ParticleVolume::get),
+        f: deformations
+          .get(entity)
+          .map_or(Matrix3f::identity(), ParticleDeformation::deformation_gradient),
+        data: (
+          mass.get(),
+          velocity.get(),
+          affines.get(entity).map(ParticleAffineVelocity::get),
+          grid.field_of(bodies.get(entity).map(ParticleBody::get)),
+        ),
+      })
+      .collect();
+
+    grid.scatter(&particles, |node, node_position, weight, grad_w, p| {
+      let (mass, velocity, affine, body) = p.data;
+
+      // The affine velocity is zero unless APIC is used
+      let mut vel = velocity;
+      if let Some(c) = affine {
+        vel += c * (node_position - p.position);
+      }
+
+      node.mass += mass * weight;
+      node.momentum += mass * vel * weight;
+
+      // The body's own field when bodies are separated
+      if let Some(body) = body {
+        let field = node.field_mut(body);
+        field.mass += mass * weight;
+        field.momentum += mass * vel * weight;
+        field.mass_gradient -= mass * grad_w;
       }
-    }
+    });
   }
 }
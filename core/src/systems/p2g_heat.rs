@@ -0,0 +1,37 @@
+use specs::prelude::*;
+
+use crate::components::*;
+use crate::resources::*;
+
+/// Particle to Grid transfer of heat. Scatters the heat capacity, the conductivity and the
+/// heat capacity weighted temperature of particles with `ParticleTemperature`
+pub struct P2GHeatSystem;
+
+impl<'a> System<'a> for P2GHeatSystem {
+  type SystemData = (
+    Write<'a, Grid>,
+    ReadStorage<'a, ParticleMass>,
+    ReadStorage<'a, ParticlePosition>,
+    ReadStorage<'a, ParticleTemperature>,
+  );
+
+  fn run(&mut self, (mut grid, masses, positions, temperatures): Self::SystemData) {
+    for (mass, position, temperature) in (&masses, &positions, &temperatures).join() {
+      let capacity = mass.get() * temperature.heat_capacity;
+      for (node_index, weight, _) in grid.neighbor_weights(position.get()) {
+        let node = grid.get_node_mut(node_index);
+        node.heat_capacity += weight * capacity;
+        node.temperature_temp += weight * capacity * temperature.get();
+        node.conductivity += weight * capacity * temperature.conductivity;
+      }
+    }
+
+    // Normalize the weighted sums
+    for node in grid.nodes.iter_mut() {
+      if node.heat_capacity > 0.0 {
+        node.temperature_temp /= node.heat_capacity;
+        node.conductivity /= node.heat_capacity;
+      }
+    }
+  }
+}
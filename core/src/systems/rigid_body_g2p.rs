@@ -0,0 +1,59 @@
+use rayon::prelude::*;
+use specs::prelude::*;
+
+use crate::components::*;
+use crate::resources::*;
+use crate::utils::*;
+
+/// Gather the linear and angular momentum of rigid bodies from the grid, and move the bodies
+/// with their new velocities. With $v_s$ the grid velocity interpolated at sample $s$ and
+/// $r_s$ its offset from the center of mass,
+///
+/// $$v = \frac{1}{M} \sum_s m_s v_s \qquad \omega = I^{-1} \sum_s m_s r_s \times v_s$$
+pub struct RigidBodyG2PSystem;
+
+impl<'a> System<'a> for RigidBodyG2PSystem {
+  type SystemData = (
+    Read<'a, DeltaTime>,
+    Read<'a, Grid>,
+    WriteStorage<'a, RigidBody>,
+    ReadStorage<'a, ParticleBody>,
+  );
+
+  fn run(&mut self, (dt, grid, mut rigid_bodies, bodies): Self::SystemData) {
+    for (rigid, body) in (&mut rigid_bodies, bodies.maybe()).join() {
+      let body = grid.field_of(body.map(ParticleBody::get));
+      let sample_mass = rigid.sample_mass();
+      let rotation = rigid.orientation;
+      let position = rigid.position;
+
+      // Gather the momentum of all the samples
+      let (momentum, angular_momentum) = rigid
+        .samples
+        .par_iter()
+        .map(|r| {
+          let offset = rotation * r;
+          let mut velocity = Vector3f::zeros();
+          for (node_index, weight, _) in grid.neighbor_weights(position + offset) {
+            velocity += weight * grid.get_node(node_index).velocities(body).1;
+          }
+          (sample_mass * velocity, sample_mass * offset.cross(&velocity))
+        })
+        .reduce(
+          || (Vector3f::zeros(), Vector3f::zeros()),
+          |(p1, l1), (p2, l2)| (p1 + p2, l1 + l2),
+        );
+
+      // New velocities of the body
+      rigid.velocity = momentum / rigid.mass;
+      rigid.angular_velocity = rigid
+        .world_inertia()
+        .try_inverse()
+        .map_or(Vector3f::zeros(), |inv| inv * angular_momentum);
+
+      // Then move the body forward
+      rigid.position += rigid.velocity * dt.get();
+      rigid.orientation = UnitQuaternionf::from_scaled_axis(rigid.angular_velocity * dt.get()) * rigid.orientation;
+    }
+  }
+}
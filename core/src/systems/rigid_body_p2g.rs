@@ -0,0 +1,38 @@
+use specs::prelude::*;
+
+use crate::components::*;
+use crate::resources::*;
+
+/// Scatter the mass and momentum of rigid bodies to the grid. Each sample is transferred
+/// like an APIC particle with the affine velocity of the rigid motion, so the momentum
+/// scattered to node $i$ is $w_{is} m_s (v + \omega \times (x_i - x))$
+pub struct RigidBodyP2GSystem;
+
+impl<'a> System<'a> for RigidBodyP2GSystem {
+  type SystemData = (
+    Write<'a, Grid>,
+    ReadStorage<'a, RigidBody>,
+    ReadStorage<'a, ParticleBody>,
+  );
+
+  fn run(&mut self, (mut grid, rigid_bodies, bodies): Self::SystemData) {
+    for (rigid, body) in (&rigid_bodies, bodies.maybe()).join() {
+      let body = grid.field_of(body.map(ParticleBody::get));
+      let sample_mass = rigid.sample_mass();
+      for offset in rigid.sample_offsets() {
+        for (node_index, weight, grad_w) in grid.neighbor_weights(rigid.position + offset) {
+          let momentum = sample_mass * weight * rigid.velocity_at(&(grid.node_position(node_index) - rigid.position));
+          let node = grid.get_node_mut(node_index);
+          node.mass += sample_mass * weight;
+          node.momentum += momentum;
+          if let Some(body) = body {
+            let field = node.field_mut(body);
+            field.mass += sample_mass * weight;
+            field.momentum += momentum;
+            field.mass_gradient -= sample_mass * grad_w;
+          }
+        }
+      }
+    }
+  }
+}
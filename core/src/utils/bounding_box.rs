@@ -37,9 +37,56 @@ impl BoundingBox {
     }
   }
 
-  pub fn gen_poisson_samples(&self, radius: f32) -> impl Iterator<Item = Vector3f> {
+  pub fn gen_poisson_samples(&self, radius: Float) -> impl Iterator<Item = Vector3f> {
     let min_vec = Math::vector_of_point(&self.min);
-    let sampler = poisson::Sampler3f::new().with_size(self.size()).with_radius(radius);
+    let sampler = poisson::Sampler::<Float, na::U3>::new()
+      .with_size(self.size())
+      .with_radius(radius);
+    sampler.generate().map(move |sample| sample + min_vec)
+  }
+}
+
+/// The axis-aligned bounding box of a 2D region
+#[derive(Debug, Copy, Clone)]
+pub struct BoundingBox2D {
+  pub min: Point2f,
+  pub max: Point2f,
+}
+
+impl BoundingBox2D {
+  pub fn new(min: Point2f, max: Point2f) -> Self {
+    Self { min, max }
+  }
+
+  pub fn size(&self) -> Vector2f {
+    self.max - self.min
+  }
+
+  pub fn transform(&self, transf: &Similarity2f) -> Self {
+    let corners = [
+      Point2f::new(self.min.x, self.min.y),
+      Point2f::new(self.max.x, self.min.y),
+      Point2f::new(self.min.x, self.max.y),
+      Point2f::new(self.max.x, self.max.y),
+    ];
+    let first = transf * corners[0];
+    let (mut min, mut max) = (first.coords, first.coords);
+    for corner in &corners[1..] {
+      let p = transf * corner;
+      min = Math::component_min(&min, &p.coords);
+      max = Math::component_max(&max, &p.coords);
+    }
+    Self {
+      min: Point2f::from(min),
+      max: Point2f::from(max),
+    }
+  }
+
+  pub fn gen_poisson_samples(&self, radius: Float) -> impl Iterator<Item = Vector2f> {
+    let min_vec = self.min.coords;
+    let sampler = poisson::Sampler::<Float, na::U2>::new()
+      .with_size(self.size())
+      .with_radius(radius);
     sampler.generate().map(move |sample| sample + min_vec)
   }
 }
@@ -0,0 +1,216 @@
+use super::*;
+
+/// A hyperelastic constitutive model, given by its energy density $\Psi(F)$ and the
+/// first Piola-Kirchhoff stress $P = \frac{\partial \Psi}{\partial F}$. The Lame
+/// parameters are passed in so that they can be changed per particle (e.g. by hardening).
+pub trait ConstitutiveModel: Send + Sync {
+  /// The elastic energy density $\Psi(F)$
+  fn energy_density(&self, f: &Matrix3f, mu: Float, lambda: Float) -> Float;
+
+  /// The first Piola-Kirchhoff stress $P(F)$
+  fn first_piola_kirchhoff(&self, f: &Matrix3f, mu: Float, lambda: Float) -> Matrix3f;
+
+  /// The differential $dP = \frac{\partial P}{\partial F} : dF$ of the stress in
+  /// direction `df`. `None` if the model does not provide it.
+  fn first_piola_kirchhoff_differential(
+    &self,
+    _f: &Matrix3f,
+    _df: &Matrix3f,
+    _mu: Float,
+    _lambda: Float,
+  ) -> Option<Matrix3f> {
+    None
+  }
+}
+
+/// Fixed corotated elasticity from [Stomakhin et al. 2012](https://www.math.ucla.edu/~jteran/papers/SSCTS13.pdf)
+///
+/// $$\Psi = \mu \|F - R\|^2 + \frac{\lambda}{2} (J - 1)^2$$
+#[derive(Copy, Clone, Debug)]
+pub struct FixedCorotated;
+
+impl ConstitutiveModel for FixedCorotated {
+  fn energy_density(&self, f: &Matrix3f, mu: Float, lambda: Float) -> Float {
+    let r = Math::polar_rotation(f);
+    let j = f.determinant();
+    mu * (f - r).norm_squared() + 0.5 * lambda * (j - 1.0) * (j - 1.0)
+  }
+
+  fn first_piola_kirchhoff(&self, f: &Matrix3f, mu: Float, lambda: Float) -> Matrix3f {
+    let r = Math::polar_rotation(f);
+    let j = f.determinant();
+    2.0 * mu * (f - r) + lambda * (j - 1.0) * Math::cofactor(f)
+  }
+
+  /// With $F = U \Sigma V^T$ and $M = U^T dF V$, the rotation differential is
+  /// $dR = U \Omega V^T$ where $\Omega_{ij} = \frac{M_{ij} - M_{ji}}{\sigma_i + \sigma_j}$
+  fn first_piola_kirchhoff_differential(
+    &self,
+    f: &Matrix3f,
+    df: &Matrix3f,
+    mu: Float,
+    lambda: Float,
+  ) -> Option<Matrix3f> {
+    let (u, sigma, v) = Math::svd3(f);
+    let m = u.transpose() * df * v;
+    let mut omega = Matrix3f::zeros();
+    for (i, j) in [(0, 1), (0, 2), (1, 2)].iter().cloned() {
+      let denom = sigma[i] + sigma[j];
+      let w = if denom.abs() > 1e-6 {
+        (m[(i, j)] - m[(j, i)]) / denom
+      } else {
+        0.0
+      };
+      omega[(i, j)] = w;
+      omega[(j, i)] = -w;
+    }
+    let dr = u * omega * v.transpose();
+    let j = f.determinant();
+    let cof = Math::cofactor(f);
+    let dj = cof.dot(df);
+    let dcof = Math::cofactor_differential(f, df);
+    Some(2.0 * mu * (df - dr) + lambda * dj * cof + lambda * (j - 1.0) * dcof)
+  }
+}
+
+/// Compressible Neo-Hookean elasticity
+///
+/// $$\Psi = \frac{\mu}{2} (tr(F^T F) - 3) - \mu \log J + \frac{\lambda}{2} (\log J)^2$$
+#[derive(Copy, Clone, Debug)]
+pub struct NeoHookean;
+
+impl NeoHookean {
+  /// $\log J$ and $F^{-T}$, guarded against inverted elements
+  fn log_j_and_inv_t(f: &Matrix3f) -> (Float, Matrix3f) {
+    let j = Float::max(f.determinant(), 1e-6);
+    let f_inv_t = f.try_inverse().map_or(Matrix3f::zeros(), |m| m.transpose());
+    (j.ln(), f_inv_t)
+  }
+}
+
+impl ConstitutiveModel for NeoHookean {
+  fn energy_density(&self, f: &Matrix3f, mu: Float, lambda: Float) -> Float {
+    let (log_j, _) = Self::log_j_and_inv_t(f);
+    0.5 * mu * (f.norm_squared() - 3.0) - mu * log_j + 0.5 * lambda * log_j * log_j
+  }
+
+  fn first_piola_kirchhoff(&self, f: &Matrix3f, mu: Float, lambda: Float) -> Matrix3f {
+    let (log_j, f_inv_t) = Self::log_j_and_inv_t(f);
+    mu * (f - f_inv_t) + lambda * log_j * f_inv_t
+  }
+
+  fn first_piola_kirchhoff_differential(
+    &self,
+    f: &Matrix3f,
+    df: &Matrix3f,
+    mu: Float,
+    lambda: Float,
+  ) -> Option<Matrix3f> {
+    let (log_j, f_inv_t) = Self::log_j_and_inv_t(f);
+    let term = f_inv_t * df.transpose() * f_inv_t;
+    Some(mu * df + (mu - lambda * log_j) * term + lambda * f_inv_t.dot(df) * f_inv_t)
+  }
+}
+
+/// St. Venant-Kirchhoff elasticity with Green strain $E = \frac{1}{2}(F^T F - I)$
+///
+/// $$\Psi = \mu E : E + \frac{\lambda}{2} tr(E)^2$$
+#[derive(Copy, Clone, Debug)]
+pub struct StVenantKirchhoff;
+
+impl StVenantKirchhoff {
+  fn green_strain(f: &Matrix3f) -> Matrix3f {
+    0.5 * (f.transpose() * f - Matrix3f::identity())
+  }
+}
+
+impl ConstitutiveModel for StVenantKirchhoff {
+  fn energy_density(&self, f: &Matrix3f, mu: Float, lambda: Float) -> Float {
+    let e = Self::green_strain(f);
+    mu * e.norm_squared() + 0.5 * lambda * e.trace() * e.trace()
+  }
+
+  fn first_piola_kirchhoff(&self, f: &Matrix3f, mu: Float, lambda: Float) -> Matrix3f {
+    let e = Self::green_strain(f);
+    f * (2.0 * mu * e + lambda * e.trace() * Matrix3f::identity())
+  }
+
+  fn first_piola_kirchhoff_differential(
+    &self,
+    f: &Matrix3f,
+    df: &Matrix3f,
+    mu: Float,
+    lambda: Float,
+  ) -> Option<Matrix3f> {
+    let e = Self::green_strain(f);
+    let de = 0.5 * (df.transpose() * f + f.transpose() * df);
+    let s = 2.0 * mu * e + lambda * e.trace() * Matrix3f::identity();
+    let ds = 2.0 * mu * de + lambda * de.trace() * Matrix3f::identity();
+    Some(df * s + f * ds)
+  }
+}
+
+/// Linear elasticity with small strain $\epsilon = \frac{1}{2}(F + F^T) - I$. Only
+/// valid for small deformations as it is not rotation invariant.
+///
+/// $$\Psi = \mu \epsilon : \epsilon + \frac{\lambda}{2} tr(\epsilon)^2$$
+#[derive(Copy, Clone, Debug)]
+pub struct LinearElasticity;
+
+impl LinearElasticity {
+  fn small_strain(f: &Matrix3f) -> Matrix3f {
+    0.5 * (f + f.transpose()) - Matrix3f::identity()
+  }
+}
+
+impl ConstitutiveModel for LinearElasticity {
+  fn energy_density(&self, f: &Matrix3f, mu: Float, lambda: Float) -> Float {
+    let eps = Self::small_strain(f);
+    mu * eps.norm_squared() + 0.5 * lambda * eps.trace() * eps.trace()
+  }
+
+  fn first_piola_kirchhoff(&self, f: &Matrix3f, mu: Float, lambda: Float) -> Matrix3f {
+    let eps = Self::small_strain(f);
+    2.0 * mu * eps + lambda * eps.trace() * Matrix3f::identity()
+  }
+
+  fn first_piola_kirchhoff_differential(
+    &self,
+    _: &Matrix3f,
+    df: &Matrix3f,
+    mu: Float,
+    lambda: Float,
+  ) -> Option<Matrix3f> {
+    Some(mu * (df + df.transpose()) + lambda * df.trace() * Matrix3f::identity())
+  }
+}
+
+/// St. Venant-Kirchhoff elasticity with Hencky (logarithmic) strain, the elastic model used
+/// together with `DruckerPrager` plasticity. With $F = U \Sigma V^T$,
+///
+/// $$\Psi = \mu \|\log \Sigma\|^2 + \frac{\lambda}{2} tr(\log \Sigma)^2$$
+#[derive(Copy, Clone, Debug)]
+pub struct Hencky;
+
+impl Hencky {
+  /// The decomposition of `f` along with its clamped singular values and their logs
+  fn log_strain(f: &Matrix3f) -> (Matrix3f, Vector3f, Vector3f, Matrix3f) {
+    let (u, sigma, v) = Math::svd3(f);
+    let sigma = sigma.map(|s| s.max(1e-6));
+    (u, sigma, sigma.map(Float::ln), v)
+  }
+}
+
+impl ConstitutiveModel for Hencky {
+  fn energy_density(&self, f: &Matrix3f, mu: Float, lambda: Float) -> Float {
+    let (_, _, eps, _) = Self::log_strain(f);
+    mu * eps.norm_squared() + 0.5 * lambda * eps.sum() * eps.sum()
+  }
+
+  fn first_piola_kirchhoff(&self, f: &Matrix3f, mu: Float, lambda: Float) -> Matrix3f {
+    let (u, sigma, eps, v) = Self::log_strain(f);
+    let trace = eps.sum();
+    let p_hat = eps.zip_map(&sigma, |e, s| (2.0 * mu * e + lambda * trace) / s);
+    u * Matrix3f::from_diagonal(&p_hat) * v.transpose()
+  }
+}
@@ -0,0 +1,203 @@
+use super::{Float, Math};
+use std::fmt::Debug;
+
+/// The maximum number of nodes a kernel can cover along one axis
+pub const MAX_STENCIL: usize = 4;
+
+/// Weights (or weight gradients) of the nodes covered along one axis
+pub type Stencil = [Float; MAX_STENCIL];
+
+/// An interpolation kernel used to transfer quantities between particles and the grid.
+/// The 3D weight of a node is the product of the 1D weights along each axis.
+pub trait Kernel: Debug + Send + Sync {
+  /// The number of nodes covered along each axis, at most `MAX_STENCIL`
+  fn support(&self) -> usize;
+
+  /// Evaluate the 1D kernel and its derivative at signed distance `d`, in index space,
+  /// between a node and the particle
+  fn eval(&self, d: Float) -> (Float, Float);
+
+  /// Get the first covered node given `x`, a position normalized to index space
+  fn base_node(&self, x: Float) -> i32;
+
+  /// The factor `k` such that $D_p = k \Delta x^2 I$ for APIC. `None` if $D_p$ is not
+  /// constant for this kernel, in which case the weight gradient is used instead.
+  fn inertia(&self) -> Option<Float>;
+
+  /// Get 1d weight given `x` normalized to index space.
+  ///
+  /// Returns the base node index, the weights of the covered nodes, and the
+  /// weight gradients (in index space) of the covered nodes.
+  fn weights_1d(&self, x: Float) -> (i32, Stencil, Stencil) {
+    let base_node = self.base_node(x);
+    let mut w = [0.0; MAX_STENCIL];
+    let mut dw = [0.0; MAX_STENCIL];
+    for i in 0..self.support() {
+      let (wi, dwi) = self.eval(x - (base_node + i as i32) as Float);
+      w[i] = wi;
+      dw[i] = dwi;
+    }
+    (base_node, w, dw)
+  }
+}
+
+/// Linear (tent) kernel covering 2 nodes per axis. Cheap but the weight gradient
+/// is discontinuous across cells, so it is mostly useful for quick previews.
+#[derive(Copy, Clone, Debug)]
+pub struct LinearKernel;
+
+impl Kernel for LinearKernel {
+  fn support(&self) -> usize {
+    2
+  }
+
+  fn eval(&self, d: Float) -> (Float, Float) {
+    let a = d.abs();
+    if a < 1.0 {
+      (1.0 - a, -d.signum())
+    } else {
+      (0.0, 0.0)
+    }
+  }
+
+  fn base_node(&self, x: Float) -> i32 {
+    x.floor() as i32
+  }
+
+  fn inertia(&self) -> Option<Float> {
+    None
+  }
+
+  /// The gradient is one-sided at the nodes, so evaluate the two covered
+  /// nodes directly to keep the gradients summing up to zero
+  fn weights_1d(&self, x: Float) -> (i32, Stencil, Stencil) {
+    let base_node = self.base_node(x);
+    let f = x - base_node as Float;
+    (base_node, [1.0 - f, f, 0.0, 0.0], [-1.0, 1.0, 0.0, 0.0])
+  }
+}
+
+/// Quadratic B-spline kernel covering 3 nodes per axis
+#[derive(Copy, Clone, Debug)]
+pub struct QuadraticKernel;
+
+impl Kernel for QuadraticKernel {
+  fn support(&self) -> usize {
+    3
+  }
+
+  fn eval(&self, d: Float) -> (Float, Float) {
+    let a = d.abs();
+    if a < 0.5 {
+      (0.75 - a * a, -2.0 * d)
+    } else if a < 1.5 {
+      let z = 1.5 - a;
+      (0.5 * z * z, -z * d.signum())
+    } else {
+      (0.0, 0.0)
+    }
+  }
+
+  fn base_node(&self, x: Float) -> i32 {
+    (x - 0.5).floor() as i32
+  }
+
+  fn inertia(&self) -> Option<Float> {
+    Some(0.25)
+  }
+}
+
+/// Cubic B-spline kernel covering 4 nodes per axis. Gives smoother stress
+/// fields at the cost of more neighbors per particle
+#[derive(Copy, Clone, Debug)]
+pub struct CubicKernel;
+
+impl Kernel for CubicKernel {
+  fn support(&self) -> usize {
+    4
+  }
+
+  fn eval(&self, d: Float) -> (Float, Float) {
+    let a = d.abs();
+    if a < 1.0 {
+      (0.5 * a * a * a - a * a + 2.0 / 3.0, (1.5 * a - 2.0) * d)
+    } else if a < 2.0 {
+      let z = 2.0 - a;
+      (z * z * z / 6.0, -0.5 * z * z * d.signum())
+    } else {
+      (0.0, 0.0)
+    }
+  }
+
+  fn base_node(&self, x: Float) -> i32 {
+    x.floor() as i32 - 1
+  }
+
+  fn inertia(&self) -> Option<Float> {
+    Some(1.0 / 3.0)
+  }
+}
+
+/// Generalized Interpolation MPM kernel: the linear kernel convolved with the
+/// characteristic function of a particle of half size `half_size`, in index space.
+/// Built per particle by `Grid::particle_weights` from the particle volume.
+#[derive(Copy, Clone, Debug)]
+pub struct GimpKernel {
+  pub half_size: Float,
+}
+
+impl GimpKernel {
+  /// The particle must not be larger than a cell for the kernel to cover at most 3 nodes
+  pub fn new(half_size: Float) -> Self {
+    Self {
+      half_size: Math::clamp(half_size, 0.0, 0.5),
+    }
+  }
+}
+
+impl Kernel for GimpKernel {
+  fn support(&self) -> usize {
+    3
+  }
+
+  fn eval(&self, d: Float) -> (Float, Float) {
+    let (a, lp) = (d.abs(), self.half_size);
+    if lp <= Float::EPSILON {
+      LinearKernel.eval(d)
+    } else if a < lp {
+      (1.0 - (d * d + lp * lp) / (2.0 * lp), -d / lp)
+    } else if a < 1.0 - lp {
+      (1.0 - a, -d.signum())
+    } else if a < 1.0 + lp {
+      let z = 1.0 + lp - a;
+      (z * z / (4.0 * lp), -z * d.signum() / (2.0 * lp))
+    } else {
+      (0.0, 0.0)
+    }
+  }
+
+  fn base_node(&self, x: Float) -> i32 {
+    (x - 1.0 - self.half_size).floor() as i32 + 1
+  }
+
+  fn inertia(&self) -> Option<Float> {
+    None
+  }
+}
+
+/// How the weights between a particle and its neighbor nodes are computed
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub enum ShapeFunction {
+  /// Point-wise evaluation of the grid's `Kernel` at the particle position
+  #[default]
+  Kernel,
+
+  /// Generalized Interpolation MPM. The particle occupies an axis-aligned cube
+  /// of its `ParticleVolume` over which the linear kernel is averaged
+  Gimp,
+
+  /// Convected Particle Domain Interpolation. The particle domain is the cube
+  /// of GIMP deformed by the particle's deformation gradient, and the linear
+  /// kernel is averaged over its corners
+  Cpdi,
+}
@@ -1,6 +1,18 @@
 use na::{allocator::*, *};
 
-pub type Vector3f = Vector3<f32>;
+/// The floating point type of the simulation. `f32` by default, `f64` with the `f64` feature
+#[cfg(not(feature = "f64"))]
+pub type Float = f32;
+
+/// The floating point type of the simulation. `f32` by default, `f64` with the `f64` feature
+#[cfg(feature = "f64")]
+pub type Float = f64;
+
+pub type Vector2f = Vector2<Float>;
+
+pub type Vector2u = Vector2<usize>;
+
+pub type Vector3f = Vector3<Float>;
 
 pub type Vector3i = Vector3<i32>;
 
@@ -8,55 +20,171 @@ pub type Vector3u = Vector3<usize>;
 
 pub type Vector4u = Vector4<usize>;
 
-pub type Point3f = Point3<f32>;
+pub type Point2f = Point2<Float>;
+
+pub type Point3f = Point3<Float>;
+
+pub type Matrix3f = Matrix3<Float>;
+
+pub type Quaternionf = Quaternion<Float>;
+
+pub type UnitQuaternionf = UnitQuaternion<Float>;
 
-pub type Matrix3f = Matrix3<f32>;
+pub type Translation2f = Translation2<Float>;
 
-pub type Quaternionf = Quaternion<f32>;
+pub type Translation3f = Translation3<Float>;
 
-pub type Translation3f = Translation3<f32>;
+pub type Rotation3f = Rotation3<Float>;
 
-pub type Rotation3f = Rotation3<f32>;
+pub type Isometry3f = Isometry3<Float>;
 
-pub type Isometry3f = Isometry3<f32>;
+pub type Similarity2f = Similarity2<Float>;
 
-pub type Similarity3f = Similarity3<f32>;
+pub type Similarity3f = Similarity3<Float>;
 
-pub type Affine3f = Affine3<f32>;
+pub type Affine3f = Affine3<Float>;
 
-pub type Transform3f = Transform3<f32>;
+pub type Transform3f = Transform3<Float>;
 
 pub struct Math;
 
 impl Math {
-  pub fn clamp(n: f32, low: f32, up: f32) -> f32 {
-    f32::min(f32::max(n, low), up)
+  pub fn clamp(n: Float, low: Float, up: Float) -> Float {
+    Float::min(Float::max(n, low), up)
   }
 
-  pub fn clamp_vec<D>(v: &VectorN<f32, D>, low: f32, up: f32) -> VectorN<f32, D>
+  pub fn clamp_vec<D>(v: &VectorN<Float, D>, low: Float, up: Float) -> VectorN<Float, D>
   where
     D: Dim + DimName,
-    DefaultAllocator: Allocator<f32, D>,
+    DefaultAllocator: Allocator<Float, D>,
   {
     v.map(|x| Self::clamp(x, low, up))
   }
 
-  pub fn component_min<D>(v1: &VectorN<f32, D>, v2: &VectorN<f32, D>) -> VectorN<f32, D>
+  pub fn component_min<D>(v1: &VectorN<Float, D>, v2: &VectorN<Float, D>) -> VectorN<Float, D>
   where
     D: Dim + DimName,
-    DefaultAllocator: Allocator<f32, D>,
+    DefaultAllocator: Allocator<Float, D>,
   {
     v1.zip_map(v2, |x1, x2| x1.min(x2))
   }
 
-  pub fn component_max<D>(v1: &VectorN<f32, D>, v2: &VectorN<f32, D>) -> VectorN<f32, D>
+  pub fn component_max<D>(v1: &VectorN<Float, D>, v2: &VectorN<Float, D>) -> VectorN<Float, D>
   where
     D: Dim + DimName,
-    DefaultAllocator: Allocator<f32, D>,
+    DefaultAllocator: Allocator<Float, D>,
   {
     v1.zip_map(v2, |x1, x2| x1.max(x2))
   }
 
+  /// Singular value decomposition $F = U \Sigma V^T$ where $U$ and $V$ are both
+  /// rotations. To ensure that, the sign of the smallest singular value is flipped
+  /// when $F$ contains a reflection. The singular values are sorted by decreasing
+  /// magnitude.
+  ///
+  /// This is an iterative one-sided Jacobi SVD: sweeps of Jacobi rotations $V$ on the
+  /// columns of $B = F V$ make them orthogonal, working on $F$ directly rather than on
+  /// $F^T F$ so that small singular values keep their accuracy. The QR decomposition of
+  /// $B$ by Givens rotations then gives $U$ and $\Sigma$. At most `MAX_SWEEPS` sweeps are
+  /// done, so this never fails, even for singular or non finite $F$.
+  pub fn svd3(f: &Matrix3f) -> (Matrix3f, Vector3f, Matrix3f) {
+    const MAX_SWEEPS: usize = 12;
+    const PAIRS: [(usize, usize); 3] = [(0, 1), (0, 2), (1, 2)];
+
+    // Orthogonalize the columns of $B = F V$ pair by pair
+    let mut b = *f;
+    let mut v = Matrix3f::identity();
+    for _ in 0..MAX_SWEEPS {
+      let mut rotated = false;
+      for &(p, q) in &PAIRS {
+        let (alpha, beta) = (b.column(p).norm_squared(), b.column(q).norm_squared());
+        let gamma = b.column(p).dot(&b.column(q));
+
+        // Non finite columns never become orthogonal, so they are skipped too
+        let tolerance = Float::EPSILON * (alpha * beta).sqrt();
+        if gamma.abs() <= tolerance || !gamma.is_finite() || !tolerance.is_finite() {
+          continue;
+        }
+        rotated = true;
+
+        // The rotation zeroing $(B^T B)_{pq}$
+        let zeta = (beta - alpha) / (2.0 * gamma);
+        let t = zeta.signum() / (zeta.abs() + (zeta * zeta + 1.0).sqrt());
+        let c = 1.0 / (t * t + 1.0).sqrt();
+        let sn = t * c;
+        for m in &mut [&mut b, &mut v] {
+          for k in 0..3 {
+            let (x, y) = (m[(k, p)], m[(k, q)]);
+            m[(k, p)] = c * x - sn * y;
+            m[(k, q)] = sn * x + c * y;
+          }
+        }
+      }
+      if !rotated {
+        break;
+      }
+    }
+
+    // Sort the columns of $B$ by decreasing norm, flipping a column at each swap to keep $V$
+    // a rotation
+    for &(i, j) in &[(0, 1), (1, 2), (0, 1)] {
+      if b.column(i).norm_squared() < b.column(j).norm_squared() {
+        b.swap_columns(i, j);
+        v.swap_columns(i, j);
+        b.column_mut(j).neg_mut();
+        v.column_mut(j).neg_mut();
+      }
+    }
+
+    // QR decomposition $B = U R$, where $R$ is diagonal as the columns of $B$ are orthogonal.
+    // Each rotation makes the diagonal element of its pivot non negative, so only the last
+    // one carries the sign of $det(F)$
+    let mut u = Matrix3f::identity();
+    for &(p, q) in &PAIRS {
+      let (x, y) = (b[(p, p)], b[(q, p)]);
+      let h = x.hypot(y);
+      let (c, sn) = if h > 0.0 { (x / h, y / h) } else { (1.0, 0.0) };
+      let mut g = Matrix3f::identity();
+      g[(p, p)] = c;
+      g[(p, q)] = sn;
+      g[(q, p)] = -sn;
+      g[(q, q)] = c;
+      b = g * b;
+      u *= g.transpose();
+    }
+    (u, b.diagonal(), v)
+  }
+
+  /// Find the rotation $R = U V^T$ of the polar decomposition $F = R S$
+  pub fn polar_rotation(f: &Matrix3f) -> Matrix3f {
+    let (u, _, v) = Self::svd3(f);
+    u * v.transpose()
+  }
+
+  /// The cofactor matrix $J F^{-T}$, which is also the derivative of the
+  /// Jacobian $J = det(F)$ with respect to $F$.
+  ///
+  /// With $F = [f_0\ f_1\ f_2]$ by columns, $J = f_0 \cdot (f_1 \times f_2)$, so
+  ///
+  /// $$\frac{dJ}{dF} = [f_1 \times f_2\ \ f_2 \times f_0\ \ f_0 \times f_1]$$
+  ///
+  /// This does not require $F$ to be invertible.
+  pub fn cofactor(f: &Matrix3f) -> Matrix3f {
+    let (f0, f1, f2) = (f.column(0), f.column(1), f.column(2));
+    Matrix3f::from_columns(&[f1.cross(&f2), f2.cross(&f0), f0.cross(&f1)])
+  }
+
+  /// The differential of the cofactor matrix $J F^{-T}$ in direction $dF$
+  pub fn cofactor_differential(f: &Matrix3f, df: &Matrix3f) -> Matrix3f {
+    let (f0, f1, f2) = (f.column(0), f.column(1), f.column(2));
+    let (d0, d1, d2) = (df.column(0), df.column(1), df.column(2));
+    Matrix3f::from_columns(&[
+      d1.cross(&f2) + f1.cross(&d2),
+      d2.cross(&f0) + f2.cross(&d0),
+      d0.cross(&f1) + f0.cross(&d1),
+    ])
+  }
+
   pub fn point_of_vector(v: &Vector3f) -> Point3f {
     Point3f::new(v.x, v.y, v.z)
   }
@@ -1,13 +1,21 @@
 mod bounding_box;
+mod constitutive_model;
+mod kernel;
 mod math;
 mod msh;
+mod plasticity;
 mod random;
 mod region;
+mod region_2d;
 mod wall;
 
 pub use bounding_box::*;
+pub use constitutive_model::*;
+pub use kernel::*;
 pub use math::*;
 pub use msh::*;
+pub use plasticity::*;
 pub use random::*;
 pub use region::*;
+pub use region_2d::*;
 pub use wall::*;
@@ -1,6 +1,6 @@
-use super::Point3f;
+use super::{Float, Point3f};
 use msh_rs::Node;
 
 pub fn msh_node_to_point(node: &Node) -> Point3f {
-  Point3f::new(node.x as f32, node.y as f32, node.z as f32)
+  Point3f::new(node.x as Float, node.y as Float, node.z as Float)
 }
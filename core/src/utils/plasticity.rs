@@ -0,0 +1,230 @@
+use super::*;
+
+/// A plasticity model acting on the singular values of the elastic deformation gradient.
+/// After each step, the trial singular values are projected back into the elastic region
+/// and the rest of the deformation is moved into $F_P$.
+pub trait PlasticityModel: Send + Sync {
+  /// Project the trial singular values `sigma` onto the yield surface, returning the new
+  /// elastic singular values. `state` is the scalar plastic state carried by the particle.
+  fn project(&self, sigma: &Vector3f, mu: Float, lambda: Float, state: &mut Float) -> Vector3f;
+}
+
+/// Drucker-Prager plasticity for granular materials from Klar et al. 2016, "Drucker-Prager
+/// Elastoplasticity for Sand Animation", with the volume correction of Tampubolon et al. 2017.
+/// Meant to be used together with the `Hencky` elastic model, as the return mapping is done
+/// in log strain space.
+///
+/// The plastic state is the log of the volume gained by the particle when separating, which
+/// is given back when it gets compressed again (only with volume correction).
+#[derive(Copy, Clone, Debug)]
+pub struct DruckerPrager {
+  /// Friction angle in degrees
+  pub friction_angle: Float,
+
+  /// Cohesion, as the volumetric log strain the material can sustain under tension
+  pub cohesion: Float,
+
+  /// Whether to keep track of the volume gained when separating
+  pub volume_correction: bool,
+}
+
+impl DruckerPrager {
+  pub fn new(friction_angle: Float) -> Self {
+    Self {
+      friction_angle,
+      cohesion: 0.0,
+      volume_correction: true,
+    }
+  }
+
+  pub fn with_cohesion(mut self, cohesion: Float) -> Self {
+    self.cohesion = cohesion;
+    self
+  }
+
+  pub fn with_volume_correction(mut self, volume_correction: bool) -> Self {
+    self.volume_correction = volume_correction;
+    self
+  }
+
+  /// The slope of the yield cone $\alpha = \sqrt{\frac{2}{3}} \frac{2 \sin \phi}{3 - \sin \phi}$
+  pub fn alpha(&self) -> Float {
+    let sin_phi = self.friction_angle.to_radians().sin();
+    (2.0 / 3.0 as Float).sqrt() * 2.0 * sin_phi / (3.0 - sin_phi)
+  }
+}
+
+impl PlasticityModel for DruckerPrager {
+  fn project(&self, sigma: &Vector3f, mu: Float, lambda: Float, state: &mut Float) -> Vector3f {
+    // Log strain, shifted by cohesion so that the tip of the cone is at the origin
+    let shift = self.cohesion / 3.0;
+    let mut eps = sigma.map(|s| s.max(1e-6).ln() - shift);
+    if self.volume_correction {
+      eps.add_scalar_mut(*state / 3.0);
+    }
+    let trace = eps.sum();
+
+    if trace >= 0.0 {
+      // Separating: project to the tip of the cone, remembering the volume gained
+      *state = if self.volume_correction { trace } else { 0.0 };
+      return Vector3f::repeat(shift.exp());
+    }
+
+    // The volume correction has been absorbed into the strain
+    *state = 0.0;
+    let dev = eps.add_scalar(-trace / 3.0);
+    let dev_norm = dev.norm();
+    if mu > 0.0 && dev_norm > 0.0 {
+      let delta_gamma = dev_norm + (3.0 * lambda + 2.0 * mu) / (2.0 * mu) * trace * self.alpha();
+      if delta_gamma > 0.0 {
+        // Outside of the cone: project to its surface along the deviatoric direction
+        eps -= (delta_gamma / dev_norm) * dev;
+      }
+    }
+    eps.map(|e| (e + shift).exp())
+  }
+}
+
+/// The singular value clamp of [Stomakhin et al. 2013](https://www.math.ucla.edu/~jteran/papers/SSCTS13.pdf),
+/// used for snow. Particles without a plasticity model use it with the `theta_c` and `theta_s`
+/// of their `ParticleDeformation`. It has no plastic state.
+#[derive(Copy, Clone, Debug)]
+pub struct SnowPlasticity {
+  /// Compression limit
+  pub theta_c: Float,
+
+  /// Stretch limit
+  pub theta_s: Float,
+}
+
+impl SnowPlasticity {
+  pub fn new(theta_c: Float, theta_s: Float) -> Self {
+    Self { theta_c, theta_s }
+  }
+}
+
+impl PlasticityModel for SnowPlasticity {
+  fn project(&self, sigma: &Vector3f, _: Float, _: Float, _: &mut Float) -> Vector3f {
+    Math::clamp_vec(sigma, 1.0 - self.theta_c, 1.0 + self.theta_s)
+  }
+}
+
+/// Von Mises plasticity with linear isotropic hardening for metal-like materials. The yield
+/// surface is $\|\tau_{dev}\| \leq \sqrt{\frac{2}{3}} (\sigma_y + H \bar\epsilon_p)$ on the
+/// Kirchhoff stress. Meant to be used together with the `Hencky` elastic model.
+///
+/// The plastic state is the accumulated equivalent plastic strain $\bar\epsilon_p$.
+#[derive(Copy, Clone, Debug)]
+pub struct VonMises {
+  /// Initial yield stress
+  pub yield_stress: Float,
+
+  /// Hardening modulus, 0 for perfect plasticity
+  pub hardening_modulus: Float,
+}
+
+impl VonMises {
+  pub fn new(yield_stress: Float) -> Self {
+    Self {
+      yield_stress,
+      hardening_modulus: 0.0,
+    }
+  }
+
+  pub fn with_hardening(mut self, hardening_modulus: Float) -> Self {
+    self.hardening_modulus = hardening_modulus;
+    self
+  }
+}
+
+impl PlasticityModel for VonMises {
+  fn project(&self, sigma: &Vector3f, mu: Float, _: Float, state: &mut Float) -> Vector3f {
+    let eps = sigma.map(|s| s.max(1e-6).ln());
+    let trace = eps.sum();
+    let dev = eps.add_scalar(-trace / 3.0);
+    let dev_norm = dev.norm();
+
+    // Radial return of the deviatoric stress $2 \mu \epsilon_{dev}$
+    let radius = (2.0 / 3.0 as Float).sqrt() * (self.yield_stress + self.hardening_modulus * *state);
+    let yield_value = 2.0 * mu * dev_norm - radius;
+    if mu <= 0.0 || dev_norm <= 0.0 || yield_value <= 0.0 {
+      return *sigma;
+    }
+    let delta_gamma = yield_value / (2.0 * mu + 2.0 / 3.0 * self.hardening_modulus);
+    *state += (2.0 / 3.0 as Float).sqrt() * delta_gamma;
+    let eps = dev * (1.0 - delta_gamma / dev_norm) + Vector3f::repeat(trace / 3.0);
+    eps.map(Float::exp)
+  }
+}
+
+/// Non-associative Cam-Clay plasticity for clay-like materials, as in Gaume et al. 2018,
+/// "Dynamic anticrack propagation in snow". With $p$ the pressure and $q$ the von Mises
+/// equivalent stress, the yield surface is the ellipse
+///
+/// $$(1 + 2\beta) q^2 + M^2 (p + \beta p_0)(p - p_0) \leq 0$$
+///
+/// where $p_0 = \kappa \sinh(\xi \max(-\log J_P, 0))$ hardens with compaction. Stresses are
+/// projected at constant pressure when possible. Meant to be used together with the `Hencky`
+/// elastic model.
+///
+/// The plastic state is the plastic volume change $\log J_P$.
+#[derive(Copy, Clone, Debug)]
+pub struct CamClay {
+  /// M, the slope of the critical state line
+  pub m: Float,
+
+  /// Ratio between the tensile and compressive strength
+  pub beta: Float,
+
+  /// Hardening factor, 0 for no hardening
+  pub hardening: Float,
+}
+
+impl CamClay {
+  pub fn new(m: Float, beta: Float, hardening: Float) -> Self {
+    Self { m, beta, hardening }
+  }
+}
+
+impl PlasticityModel for CamClay {
+  fn project(&self, sigma: &Vector3f, mu: Float, lambda: Float, state: &mut Float) -> Vector3f {
+    let kappa = lambda + 2.0 / 3.0 * mu;
+    if mu <= 0.0 || kappa <= 0.0 {
+      return *sigma;
+    }
+
+    // Pressure and deviatoric stress of the trial Hencky strain
+    let eps = sigma.map(|s| s.max(1e-6).ln());
+    let trace = eps.sum();
+    let dev = eps.add_scalar(-trace / 3.0);
+    let p = -kappa * trace;
+    let q = (1.5 as Float).sqrt() * 2.0 * mu * dev.norm();
+
+    let p0 = kappa * (1e-5 + (self.hardening * Float::max(-*state, 0.0)).sinh());
+    let (m2, beta) = (self.m * self.m, self.beta);
+    let (new_p, new_q) = if p > p0 {
+      // Beyond the compressive tip
+      (p0, 0.0)
+    } else if p < -beta * p0 {
+      // Beyond the tensile tip
+      (-beta * p0, 0.0)
+    } else if (1.0 + 2.0 * beta) * q * q + m2 * (p + beta * p0) * (p - p0) > 0.0 {
+      // Outside of the ellipse, keep the pressure
+      (p, (-m2 * (p + beta * p0) * (p - p0) / (1.0 + 2.0 * beta)).sqrt())
+    } else {
+      return *sigma;
+    };
+
+    // Harden with the plastic volume change
+    let new_trace = -new_p / kappa;
+    *state += trace - new_trace;
+
+    let dev_norm = dev.norm();
+    let new_dev = if dev_norm > 0.0 && q > 0.0 {
+      dev * (new_q / q)
+    } else {
+      Vector3f::zeros()
+    };
+    (new_dev + Vector3f::repeat(new_trace / 3.0)).map(Float::exp)
+  }
+}
@@ -1,12 +1,12 @@
 use super::*;
 use rand::Rng;
 
-pub fn random() -> f32 {
+pub fn random() -> Float {
   let mut rng = rand::thread_rng();
   rng.gen_range(0.0, 1.0)
 }
 
-pub fn random_point_in_sphere(center: Vector3f, radius: f32) -> Vector3f {
+pub fn random_point_in_sphere(center: Vector3f, radius: Float) -> Vector3f {
   let mut rng = rand::thread_rng();
   loop {
     let x = rng.gen_range(-radius, radius);
@@ -41,11 +41,11 @@ impl Region for Cube {
 
 #[derive(Copy, Clone, Debug)]
 pub struct Sphere {
-  pub radius: f32,
+  pub radius: Float,
 }
 
 impl Sphere {
-  pub fn new(radius: f32) -> Self {
+  pub fn new(radius: Float) -> Self {
     Self { radius }
   }
 }
@@ -126,11 +126,11 @@ impl TetMesh {
   }
 
   fn point_of_node(node: &Node) -> Point3f {
-    Point3f::new(node.x as f32, node.y as f32, node.z as f32)
+    Point3f::new(node.x as Float, node.y as Float, node.z as Float)
   }
 
   fn vector_of_node(node: &Node) -> Vector3f {
-    Vector3f::new(node.x as f32, node.y as f32, node.y as f32)
+    Vector3f::new(node.x as Float, node.y as Float, node.y as Float)
   }
 }
 
@@ -0,0 +1,101 @@
+use super::*;
+
+/// A region of the plane, used to put particles into a 2D world
+pub trait Region2D {
+  /// Returns whether the region contains the given point
+  fn contains(&self, point: &Point2f) -> bool;
+
+  /// Returns the axis-aligned bounding box of the region
+  fn bound(&self) -> BoundingBox2D;
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Circle {
+  pub radius: Float,
+}
+
+impl Circle {
+  pub fn new(radius: Float) -> Self {
+    Self { radius }
+  }
+}
+
+impl Region2D for Circle {
+  fn contains(&self, point: &Point2f) -> bool {
+    point.coords.magnitude() < self.radius
+  }
+
+  fn bound(&self) -> BoundingBox2D {
+    let p = Point2f::new(self.radius, self.radius);
+    BoundingBox2D::new(-p, p)
+  }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Rectangle {
+  pub size: Vector2f,
+  half_size: Vector2f,
+}
+
+impl Rectangle {
+  pub fn new(size: Vector2f) -> Self {
+    Self {
+      size,
+      half_size: size / 2.0,
+    }
+  }
+}
+
+impl Region2D for Rectangle {
+  fn contains(&self, point: &Point2f) -> bool {
+    point.x.abs() < self.half_size.x && point.y.abs() < self.half_size.y
+  }
+
+  fn bound(&self) -> BoundingBox2D {
+    BoundingBox2D::new(Point2f::from(-self.half_size), Point2f::from(self.half_size))
+  }
+}
+
+/// A simple polygon given by its vertices in order. Self intersecting polygons follow the
+/// even-odd rule
+#[derive(Clone, Debug)]
+pub struct Polygon {
+  vertices: Vec<Point2f>,
+  bb: BoundingBox2D,
+}
+
+impl Polygon {
+  /// Panics if there are no vertices
+  pub fn new(vertices: Vec<Point2f>) -> Self {
+    assert!(!vertices.is_empty(), "A polygon needs at least one vertex");
+    let (mut min, mut max) = (vertices[0].coords, vertices[0].coords);
+    for v in &vertices {
+      min = Math::component_min(&min, &v.coords);
+      max = Math::component_max(&max, &v.coords);
+    }
+    let bb = BoundingBox2D::new(Point2f::from(min), Point2f::from(max));
+    Self { vertices, bb }
+  }
+}
+
+impl Region2D for Polygon {
+  fn contains(&self, point: &Point2f) -> bool {
+    // Count the edges crossed by a ray going along +x from the point
+    let mut inside = false;
+    let n = self.vertices.len();
+    for i in 0..n {
+      let (a, b) = (self.vertices[i], self.vertices[(i + 1) % n]);
+      if (a.y > point.y) != (b.y > point.y) {
+        let x = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+        if point.x < x {
+          inside = !inside;
+        }
+      }
+    }
+    inside
+  }
+
+  fn bound(&self) -> BoundingBox2D {
+    self.bb
+  }
+}
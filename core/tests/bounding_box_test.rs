@@ -22,10 +22,28 @@ fn bounding_box_transf_2() {
   let transl = Translation3f::from(Vector3f::new(3.0, 3.0, 3.0));
   let rotate = UnitQuaternion::from_axis_angle(
     &Unit::new_normalize(Vector3f::new(0.0, 1.0, 0.0)),
-    std::f32::consts::FRAC_PI_2,
+    std::f64::consts::FRAC_PI_2 as Float,
   );
   let transf = Isometry3f::from_parts(transl, rotate);
   let new_bb = bb.transform(&na::convert(transf));
   assert_eq!(new_bb.min, Point3f::new(3.0, 3.0, 2.0));
   assert_eq!(new_bb.max, Point3f::new(4.0, 4.0, 3.0));
 }
+
+#[test]
+fn polygon_bound() {
+  let polygon = Polygon::new(vec![
+    Point2f::new(0.2, 0.1),
+    Point2f::new(0.5, 0.3),
+    Point2f::new(0.1, 0.4),
+  ]);
+  let bb = polygon.bound();
+  assert_eq!(bb.min, Point2f::new(0.1, 0.1));
+  assert_eq!(bb.max, Point2f::new(0.5, 0.4));
+}
+
+#[test]
+#[should_panic]
+fn polygon_needs_vertices() {
+  Polygon::new(vec![]);
+}
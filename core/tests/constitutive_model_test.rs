@@ -0,0 +1,86 @@
+use mpm_rs::*;
+
+const MU: Float = 1.0;
+const LAMBDA: Float = 2.0;
+
+/// A random deformation gradient not far from the identity, without inversion
+fn random_deformation() -> Matrix3f {
+  loop {
+    let f = Matrix3f::identity() + Matrix3f::from_fn(|_, _| 0.6 * random() - 0.3);
+    if f.determinant() > 0.3 {
+      return f;
+    }
+  }
+}
+
+fn models() -> Vec<(&'static str, Box<dyn ConstitutiveModel>)> {
+  vec![
+    ("FixedCorotated", Box::new(FixedCorotated)),
+    ("NeoHookean", Box::new(NeoHookean)),
+    ("StVenantKirchhoff", Box::new(StVenantKirchhoff)),
+    ("LinearElasticity", Box::new(LinearElasticity)),
+    ("Hencky", Box::new(Hencky)),
+  ]
+}
+
+#[test]
+fn no_stress_at_rest() {
+  let f = Matrix3f::identity();
+  for (name, model) in models() {
+    assert!(model.energy_density(&f, MU, LAMBDA).abs() < 1e-6, "{}", name);
+    assert!(model.first_piola_kirchhoff(&f, MU, LAMBDA).norm() < 1e-5, "{}", name);
+  }
+}
+
+#[test]
+fn stress_is_energy_gradient() {
+  let h = 1e-3;
+  for (name, model) in models() {
+    for _ in 0..50 {
+      let f = random_deformation();
+      let p = model.first_piola_kirchhoff(&f, MU, LAMBDA);
+      let mut expected = Matrix3f::zeros();
+      for i in 0..3 {
+        for j in 0..3 {
+          let mut df = Matrix3f::zeros();
+          df[(i, j)] = h;
+          let (plus, minus) = (f + df, f - df);
+          expected[(i, j)] =
+            (model.energy_density(&plus, MU, LAMBDA) - model.energy_density(&minus, MU, LAMBDA)) / (2.0 * h);
+        }
+      }
+      assert!(
+        (p - expected).norm() < 1e-2 * (1.0 + p.norm()),
+        "{}: P = {} but dPsi/dF = {}",
+        name,
+        p,
+        expected
+      );
+    }
+  }
+}
+
+#[test]
+fn stress_differential() {
+  let h = 1e-3;
+  for (name, model) in models() {
+    for _ in 0..50 {
+      let f = random_deformation();
+      let df = Matrix3f::from_fn(|_, _| random() - 0.5);
+      let dp = match model.first_piola_kirchhoff_differential(&f, &df, MU, LAMBDA) {
+        Some(dp) => dp,
+        None => continue,
+      };
+      let plus = model.first_piola_kirchhoff(&(f + h * df), MU, LAMBDA);
+      let minus = model.first_piola_kirchhoff(&(f - h * df), MU, LAMBDA);
+      let expected = (plus - minus) / (2.0 * h);
+      assert!(
+        (dp - expected).norm() < 1e-2 * (1.0 + dp.norm()),
+        "{}: dP = {} but expected {}",
+        name,
+        dp,
+        expected
+      );
+    }
+  }
+}
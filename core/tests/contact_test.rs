@@ -0,0 +1,64 @@
+use mpm_rs::*;
+use specs::prelude::*;
+
+/// Two soft blocks meeting at `x = 0.5`, which rebound from each other at speed `v`. Returns the gap
+/// between them and their relative velocity after `steps` steps
+fn rebound(contact: bool, v: Float, steps: usize) -> (Float, Float) {
+  let mut builder = WorldBuilder::new()
+    .with_size_2d(Vector2f::new(1.0, 0.4))
+    .with_dx(0.04)
+    .with_dt(0.0005)
+    .with_transfer_scheme(TransferScheme::Apic);
+  if contact {
+    builder = builder.with_body_contact(0.0);
+  }
+  let mut world = builder.build();
+  let left = world
+    .put_rectangle(Vector2f::new(0.34, 0.16), Vector2f::new(0.5, 0.24), 1.0)
+    .with(ParticleVelocity::new(Vector3f::new(-v, 0.0, 0.0)))
+    .with(ParticleDeformation::elastic(100.0, 0.3))
+    .first();
+  world
+    .put_rectangle(Vector2f::new(0.5, 0.16), Vector2f::new(0.66, 0.24), 1.0)
+    .with(ParticleVelocity::new(Vector3f::new(v, 0.0, 0.0)))
+    .with(ParticleDeformation::elastic(100.0, 0.3));
+  for _ in 0..steps {
+    world.step();
+  }
+
+  let (positions, velocities, bodies): (
+    ReadStorage<ParticlePosition>,
+    ReadStorage<ParticleVelocity>,
+    ReadStorage<ParticleBody>,
+  ) = world.world.system_data();
+  let left = bodies.get(left).unwrap().get();
+  let (mut left_max, mut right_min) = (Float::MIN, Float::MAX);
+  let (mut left_v, mut right_v, mut left_n, mut right_n) = (0.0, 0.0, 0.0, 0.0);
+  for (x, u, body) in (&positions, &velocities, &bodies).join() {
+    if body.get() == left {
+      left_max = left_max.max(x.get().x);
+      left_v += u.get().x;
+      left_n += 1.0;
+    } else {
+      right_min = right_min.min(x.get().x);
+      right_v += u.get().x;
+      right_n += 1.0;
+    }
+  }
+  (right_min - left_max, right_v / right_n - left_v / left_n)
+}
+
+#[test]
+fn bodies_separate_with_contact() {
+  let (gap, relative) = rebound(true, 0.5, 120);
+  assert!(gap > 0.045, "Gap of {} between the bodies", gap);
+  assert!(relative > 0.9, "Relative velocity of {}", relative);
+}
+
+#[test]
+fn bodies_stick_without_contact() {
+  // The bodies share a single velocity field on the nodes in between, which holds them together
+  let (gap, relative) = rebound(false, 0.5, 120);
+  assert!(gap < 0.04, "Gap of {} between the bodies", gap);
+  assert!(relative < 0.85, "Relative velocity of {}", relative);
+}
@@ -0,0 +1,70 @@
+use mpm_rs::*;
+
+#[test]
+fn brittle_damage() {
+  let mut damage = ParticleDamage::brittle(DamageCriterion::Strain(0.1));
+  damage.update(0.05);
+  assert_eq!(damage.get(), 0.0);
+  damage.update(0.1);
+  assert_eq!(damage.get(), 0.0);
+  damage.update(0.1001);
+  assert!(damage.is_fractured());
+}
+
+#[test]
+fn ductile_damage() {
+  let (threshold, softening) = (0.1, 0.05);
+  let mut damage = ParticleDamage::ductile(DamageCriterion::Strain(threshold), softening);
+  damage.update(threshold);
+  assert_eq!(damage.get(), 0.0);
+
+  // Starts from 0 at the threshold and grows towards 1
+  damage.update(threshold + 1e-4);
+  assert!(damage.get() > 0.0 && damage.get() < 0.01);
+  let kappa = 0.2;
+  damage.update(kappa);
+  let expected = 1.0 - threshold / kappa * (-(kappa - threshold) / softening).exp();
+  assert!((damage.get() - expected).abs() < 1e-6);
+  damage.update(100.0);
+  assert!(damage.get() <= 1.0 && damage.get() > 0.99);
+}
+
+#[test]
+fn damage_is_irreversible() {
+  let mut damage = ParticleDamage::ductile(DamageCriterion::Stress(1.0), 0.5);
+  let mut previous = 0.0;
+  for _ in 0..1000 {
+    damage.update(3.0 * random());
+    assert!(damage.get() >= previous && damage.get() <= 1.0);
+    previous = damage.get();
+  }
+  assert!(previous > 0.0);
+}
+
+#[test]
+fn damage_only_weakens_tension() {
+  let mut damage = ParticleDamage::ductile(DamageCriterion::Strain(0.1), 0.05);
+  damage.update(0.2);
+  let d = damage.get();
+  assert!(d > 0.0);
+  let stretched = Matrix3f::from_diagonal(&Vector3f::new(1.2, 1.0, 1.0));
+  let compressed = Matrix3f::from_diagonal(&Vector3f::new(0.8, 1.0, 1.0));
+  let sheared = Matrix3f::new(1.0, 0.5, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0);
+  assert_eq!(damage.stress_factor(&stretched), 1.0 - d);
+  assert_eq!(damage.stress_factor(&compressed), 1.0);
+  assert_eq!(damage.stress_factor(&sheared), 1.0);
+}
+
+#[test]
+fn damage_criteria() {
+  let mut def = ParticleDeformation::elastic(1000.0, 0.3);
+  let (strain, stress) = (DamageCriterion::Strain(0.1), DamageCriterion::Stress(1.0));
+  assert!(strain.evaluate(&def, &FixedCorotated).abs() < 1e-5);
+  assert!(stress.evaluate(&def, &FixedCorotated).abs() < 1e-3);
+
+  def.f_elastic = Matrix3f::from_diagonal(&Vector3f::new(1.2, 1.0, 1.0));
+  assert!((strain.evaluate(&def, &FixedCorotated) - 0.2).abs() < 1e-5);
+  assert!(stress.evaluate(&def, &FixedCorotated) > 0.0);
+  def.f_elastic = Matrix3f::from_diagonal(&Vector3f::new(0.8, 0.8, 0.8));
+  assert!(stress.evaluate(&def, &FixedCorotated) < 0.0);
+}
@@ -0,0 +1,72 @@
+use mpm_rs::*;
+use specs::prelude::*;
+
+/// Let a free square of fluid flow for `steps` steps, starting with the shear flow
+/// $v_x = \dot\gamma (y - y_c)$. Returns the mean J of the particles and the shear rate fitted
+/// from their velocities
+fn fluid_block(fluid: ParticleFluid, shear_rate: Float, steps: usize) -> (Float, Float) {
+  let mut world = WorldBuilder::new()
+    .with_size_2d(Vector2f::new(0.6, 0.6))
+    .with_dx(0.04)
+    .with_dt(0.001)
+    .with_transfer_scheme(TransferScheme::Apic)
+    .build();
+  world
+    .put_rectangle(Vector2f::new(0.22, 0.22), Vector2f::new(0.38, 0.38), 1.0)
+    .with(fluid);
+  {
+    let (positions, mut velocities, mut affines): (
+      ReadStorage<ParticlePosition>,
+      WriteStorage<ParticleVelocity>,
+      WriteStorage<ParticleAffineVelocity>,
+    ) = world.world.system_data();
+    for (x, v, c) in (&positions, &mut velocities, &mut affines).join() {
+      v.set(Vector3f::new(shear_rate * (x.get().y - 0.3), 0.0, 0.0));
+      let mut grad_v = Matrix3f::zeros();
+      grad_v[(0, 1)] = shear_rate;
+      c.set(grad_v);
+    }
+  }
+  for _ in 0..steps {
+    world.step();
+  }
+
+  // Least squares fit of the velocity along x against y
+  let (positions, velocities, fluids): (
+    ReadStorage<ParticlePosition>,
+    ReadStorage<ParticleVelocity>,
+    ReadStorage<ParticleFluid>,
+  ) = world.world.system_data();
+  let (mut n, mut sj, mut sy, mut sv, mut syy, mut syv) = (0.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+  for (x, v, fluid) in (&positions, &velocities, &fluids).join() {
+    let (y, u) = (x.get().y, v.get().x);
+    n += 1.0;
+    sj += fluid.j;
+    sy += y;
+    sv += u;
+    syy += y * y;
+    syv += y * u;
+  }
+  (sj / n, (n * syv - sy * sv) / (n * syy - sy * sy))
+}
+
+#[test]
+fn pressure_restores_volume() {
+  let mut compressed = ParticleFluid::new(1000.0, 7.0);
+  compressed.j = 0.8;
+  let (j, _) = fluid_block(compressed, 0.0, 40);
+  assert!(j > 0.9, "Compressed fluid only expanded to J = {}", j);
+
+  let mut stretched = ParticleFluid::new(1000.0, 7.0);
+  stretched.j = 1.2;
+  let (j, _) = fluid_block(stretched, 0.0, 40);
+  assert!(j < 1.16, "Stretched fluid only contracted to J = {}", j);
+}
+
+#[test]
+fn viscosity_damps_shear_flow() {
+  let (_, inviscid) = fluid_block(ParticleFluid::new(1000.0, 7.0), 2.0, 20);
+  let (_, viscous) = fluid_block(ParticleFluid::new(1000.0, 7.0).with_viscosity(50.0), 2.0, 20);
+  assert!(inviscid > 1.95, "Inviscid shear rate went down to {}", inviscid);
+  assert!(viscous < 1.8, "Viscous shear rate stayed at {}", viscous);
+}
@@ -0,0 +1,266 @@
+use mpm_rs::*;
+use specs::prelude::*;
+
+/// The particle storages read by the dense accumulation
+type TransferData<'a> = (
+  Entities<'a>,
+  ReadStorage<'a, ParticleMass>,
+  ReadStorage<'a, ParticlePosition>,
+  ReadStorage<'a, ParticleVelocity>,
+  ReadStorage<'a, ParticleAffineVelocity>,
+  ReadStorage<'a, ParticleVolume>,
+);
+
+#[test]
+fn sparse_transfer_matches_dense_accumulation() {
+  let mut world = WorldBuilder::new()
+    .with_size(Vector3f::new(1.0, 1.0, 1.0))
+    .with_dx(0.05)
+    .build();
+
+  // Two clusters of particles far apart, leaving most of the blocks inactive
+  for &center in &[Vector3f::new(0.2, 0.2, 0.2), Vector3f::new(0.75, 0.6, 0.8)] {
+    for _ in 0..50 {
+      let offset = Vector3f::new(random(), random(), random()) * 0.15;
+      let velocity = Vector3f::new(random(), random(), random()) - Vector3f::repeat(0.5);
+      world
+        .put_particle(center + offset, 0.001)
+        .with(ParticleVelocity::new(velocity));
+    }
+  }
+  world.step();
+  CleanGridSystem.run_now(&world.world);
+  P2GSystem.run_now(&world.world);
+
+  // Accumulate the same transfer on a dense array of all the nodes
+  let grid = world.world.fetch::<Grid>();
+  let (entities, masses, positions, velocities, affines, volumes): TransferData = world.world.system_data();
+  let dense_index = |i: Vector3u| (i.z * grid.dim.y + i.y) * grid.dim.x + i.x;
+  let num_nodes = grid.dim.x * grid.dim.y * grid.dim.z;
+  let (mut mass, mut momentum) = (vec![0.0; num_nodes], vec![Vector3f::zeros(); num_nodes]);
+  for (entity, m, x, v) in (&entities, &masses, &positions, &velocities).join() {
+    let volume = volumes.get(entity).map_or(0.0, ParticleVolume::get);
+    for (node_index, weight, _) in grid.particle_weights(x.get(), volume, &Matrix3f::identity()) {
+      let mut velocity = v.get();
+      if let Some(c) = affines.get(entity) {
+        velocity += c.get() * (grid.node_position(node_index) - x.get());
+      }
+      mass[dense_index(node_index)] += m.get() * weight;
+      momentum[dense_index(node_index)] += m.get() * velocity * weight;
+    }
+  }
+
+  // Most of the 125 blocks stay inactive
+  assert!(grid.num_active_blocks() < 64);
+  for node_index in grid.indices() {
+    let node = grid.get_node(node_index);
+    let index = dense_index(node_index);
+    assert!(
+      (node.mass - mass[index]).abs() <= 1e-5 * mass[index],
+      "Mass of {} is {} instead of {}",
+      node_index,
+      node.mass,
+      mass[index]
+    );
+    assert!(
+      (node.momentum - momentum[index]).norm() <= 1e-5 * mass[index],
+      "Momentum of {} is {}",
+      node_index,
+      node.momentum
+    );
+  }
+}
+
+#[test]
+fn boundaries_are_kept_through_clear() {
+  let mut grid = Grid::new(Vector3u::new(12, 12, 12), 0.1);
+  let (inactive, active) = (Vector3u::new(1, 2, 3), Vector3u::new(9, 10, 11));
+
+  // One node is set before its block is ever activated, the other one while it is active
+  grid.set_boundary(inactive, Boundary::Sticky);
+  grid.set_boundary_temperature(inactive, Some(10.0));
+  grid.get_node_mut(active).mass = 1.0;
+  grid.set_boundary(active, Boundary::Sticky);
+  grid.set_boundary_temperature(active, Some(20.0));
+  assert_eq!(grid.get_node(active).boundary_temperature, Some(20.0));
+
+  grid.clear();
+  assert_eq!(grid.num_active_blocks(), 0);
+  assert_eq!(grid.get_node(active).mass, 0.0);
+  for &(node_index, temperature) in &[(inactive, 10.0), (active, 20.0)] {
+    let node = grid.get_node_mut(node_index);
+    assert!(matches!(node.boundary, Boundary::Sticky));
+    assert_eq!(node.boundary_temperature, Some(temperature));
+    assert_eq!(node.mass, 0.0);
+  }
+
+  // The other nodes of the re-activated blocks have no boundary
+  let node = grid.get_node(Vector3u::new(0, 0, 0));
+  assert!(matches!(node.boundary, Boundary::None));
+  assert_eq!(node.boundary_temperature, None);
+}
+
+type Data = (Float, Vector3f, Matrix3f, usize);
+
+/// What the transfers add to a node: the mass, momentum and force of the particle, and its
+/// body's field
+fn add_particle(node: &mut Node, weight: Float, grad_w: Vector3f, p: &ScatterParticle<Data>) {
+  let (mass, velocity, stress, body) = p.data;
+  node.mass += mass * weight;
+  node.momentum += mass * velocity * weight;
+  node.force -= stress * grad_w;
+  let field = node.field_mut(body);
+  field.mass += mass * weight;
+  field.mass_gradient += mass * grad_w;
+}
+
+#[test]
+fn scatter_matches_serial_loop() {
+  let new_grid = || {
+    Grid::new(Vector3u::new(24, 24, 24), 0.05)
+      .with_shape_function(ShapeFunction::Cpdi)
+      .with_body_contact(0.3)
+  };
+  let volume = (0.025 as Float).powi(3);
+  let mut particles: Vec<_> = (0..400)
+    .map(|i| ScatterParticle {
+      position: Vector3f::new(0.1 + random(), 0.1 + random(), 0.1 + random()),
+      volume,
+      f: Matrix3f::identity() + 0.2 * Matrix3f::from_fn(|_, _| random() - 0.5),
+      data: (
+        1e-3,
+        Vector3f::new(random(), random(), random()),
+        Matrix3f::new_random(),
+        i % 3,
+      ),
+    })
+    .collect();
+
+  // A domain stretched over more than two blocks, which is added after the parallel passes
+  particles.push(ScatterParticle {
+    position: Vector3f::new(0.35, 0.35, 0.35),
+    volume,
+    f: Matrix3f::from_diagonal(&Vector3f::new(14.0, 1.0, 1.0)),
+    data: (1e-3, Vector3f::new(1.0, 0.0, 0.0), Matrix3f::identity(), 1),
+  });
+  let mut grid = new_grid();
+  let spread = &particles[particles.len() - 1];
+  let (min, max) = grid
+    .particle_weights(spread.position, spread.volume, &spread.f)
+    .fold((usize::MAX, 0), |(min, max), (i, _, _)| (min.min(i.x), max.max(i.x)));
+  assert!(max / BLOCK_SIZE - min / BLOCK_SIZE > 1);
+
+  grid.scatter(&particles, |node, _, weight, grad_w, p| {
+    add_particle(node, weight, grad_w, p)
+  });
+  let mut serial = new_grid();
+  for p in &particles {
+    for (node_index, weight, grad_w) in serial.particle_weights(p.position, p.volume, &p.f) {
+      add_particle(serial.get_node_mut(node_index), weight, grad_w, p);
+    }
+  }
+
+  let close = |a: Vector3f, b: Vector3f| (a - b).norm() <= 1e-4 * (1e-3 + b.norm());
+  for node_index in grid.indices() {
+    let (node, expected) = (grid.get_node(node_index), serial.get_node(node_index));
+    assert!(
+      (node.mass - expected.mass).abs() <= 1e-5 * expected.mass,
+      "Mass of {}",
+      node_index
+    );
+    assert!(close(node.momentum, expected.momentum), "Momentum of {}", node_index);
+    assert!(close(node.force, expected.force), "Force of {}", node_index);
+    assert_eq!(node.fields.len(), expected.fields.len());
+    for field in &expected.fields {
+      let actual = node.field(field.body).unwrap();
+      assert!((actual.mass - field.mass).abs() <= 1e-5 * field.mass);
+      assert!(close(actual.mass_gradient, field.mass_gradient));
+    }
+  }
+}
+
+/// A 2D world of 0.4 by 0.4 with sliding walls, either from the origin or centered around it
+fn bounded_world(centered: bool) -> mpm_rs::World<'static, 'static> {
+  let builder = WorldBuilder::new().with_dx(0.04).with_dt(0.0005);
+  let builder = if centered {
+    builder.with_bounds_2d(BoundingBox2D::new(Point2f::new(-0.2, -0.2), Point2f::new(0.2, 0.2)))
+  } else {
+    builder.with_size_2d(Vector2f::new(0.4, 0.4))
+  };
+  let mut world = builder.build();
+  world.put_sliding_boundary(0.08);
+  world
+}
+
+#[test]
+fn centered_regions_are_translated() {
+  let mut world = bounded_world(true);
+  let (min, max) = (Vector2f::new(-0.08, -0.12), Vector2f::new(0.08, 0.0));
+  let (center, radius) = (Vector2f::new(0.0, 0.1), 0.05);
+  world.put_rectangle(min, max, 1.0);
+  world.put_circle(center, radius, 1.0);
+
+  // The regions are sampled where they are, around the origin, and nowhere else
+  let plane_z = world.world.fetch::<Grid>().plane_z();
+  let (positions, bodies): (ReadStorage<ParticlePosition>, ReadStorage<ParticleBody>) = world.world.system_data();
+  let mut counts = [0, 0];
+  for (x, body) in (&positions, &bodies).join() {
+    let x = x.get();
+    assert!((x.z - plane_z).abs() < 1e-6);
+    let inside = if body.get() == 0 {
+      x.x >= min.x && x.x <= max.x && x.y >= min.y && x.y <= max.y
+    } else {
+      (Vector2f::new(x.x, x.y) - center).norm() <= radius
+    };
+    assert!(inside, "Particle of body {} at {}", body.get(), x);
+    counts[body.get()] += 1;
+  }
+  assert!(counts[0] > 0 && counts[1] > 0, "Sampled {:?} particles", counts);
+}
+
+#[test]
+fn centered_world_steps_like_translated_one() {
+  let offset = Vector3f::new(0.2, 0.2, 0.0);
+  let mut worlds = [bounded_world(false), bounded_world(true)];
+
+  // The same block of particles in both worlds, thrown at the bottom left corner
+  for (world, &shift) in worlds.iter_mut().zip(&[offset, Vector3f::zeros()]) {
+    let z = world.world.fetch::<Grid>().plane_z();
+    for i in 0..8 {
+      for j in 0..8 {
+        let x = Vector3f::new(-0.1 + 0.01 * i as Float, -0.1 + 0.01 * j as Float, z) + shift;
+        world
+          .put_particle(x, 4e-6)
+          .with(ParticleVolume::new(4e-6))
+          .with(ParticleVelocity::new(Vector3f::new(-2.0, -2.0, 0.0)))
+          .with(ParticleDeformation::elastic(1000.0, 0.3));
+      }
+    }
+  }
+  for _ in 0..50 {
+    for world in &mut worlds {
+      world.step();
+    }
+  }
+
+  let positions = |world: &mpm_rs::World| -> Vec<Vector3f> {
+    world
+      .world
+      .read_storage::<ParticlePosition>()
+      .join()
+      .map(|x| x.get())
+      .collect()
+  };
+  let (shifted, centered) = (positions(&worlds[0]), positions(&worlds[1]));
+  for (a, b) in shifted.iter().zip(&centered) {
+    assert!(
+      (a - offset - b).norm() < 1e-4,
+      "Particle at {} instead of {}",
+      b,
+      a - offset
+    );
+
+    // The walls stopped the block
+    assert!(b.x > -0.13 && b.y > -0.13, "Particle at {} went through the walls", b);
+  }
+}
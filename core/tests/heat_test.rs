@@ -0,0 +1,310 @@
+use mpm_rs::*;
+use specs::prelude::*;
+
+/// A row of nodes between a hot and a cold node with fixed temperatures
+fn heated_rod(num_nodes: usize) -> specs::World {
+  let mut world = specs::World::new();
+  world.register::<ParticleTemperature>();
+  world
+    .create_entity()
+    .with(ParticleTemperature::new(0.0, 1.0, 1.0))
+    .build();
+  world.insert(DeltaTime::default());
+
+  let mut grid = Grid::new(Vector3u::new(num_nodes, 1, 1), 0.1);
+  grid.set_boundary_temperature(Vector3u::new(0, 0, 0), Some(1.0));
+  grid.set_boundary_temperature(Vector3u::new(num_nodes - 1, 0, 0), Some(0.0));
+  for i in 1..num_nodes - 1 {
+    let node = grid.get_node_mut(Vector3u::new(i, 0, 0));
+    node.heat_capacity = 1.0;
+    node.conductivity = 1000.0;
+  }
+  world.insert(grid);
+  world
+}
+
+#[test]
+fn heat_diffusion_reaches_steady_state() {
+  let num_nodes = 12;
+  let world = heated_rod(num_nodes);
+  let mut change = Float::INFINITY;
+  for _ in 0..10000 {
+    GridHeatDiffusionSystem.run_now(&world);
+    let mut grid = world.fetch_mut::<Grid>();
+    change = 0.0;
+    for node in grid.nodes.iter_mut() {
+      // Temperatures stay within the ones of the boundary
+      assert!(node.temperature >= 0.0 && node.temperature <= 1.0);
+      change = Float::max(change, (node.temperature - node.temperature_temp).abs());
+      node.temperature_temp = node.temperature;
+    }
+    if change < 1e-7 {
+      break;
+    }
+  }
+  assert!(change < 1e-6, "Still changing by {}", change);
+
+  // The steady state is linear between the fixed temperatures
+  let grid = world.fetch::<Grid>();
+  for i in 0..num_nodes {
+    let expected = 1.0 - i as Float / (num_nodes - 1) as Float;
+    let temperature = grid.get_node(Vector3u::new(i, 0, 0)).temperature;
+    assert!(
+      (temperature - expected).abs() < 1e-3,
+      "Node {} at {} instead of {}",
+      i,
+      temperature,
+      expected
+    );
+  }
+}
+
+/// Scatter the temperatures of particles at the given positions to the grid and gather them back,
+/// without any heat diffusion in between. Returns the heat of the particles, the heat on the grid
+/// and the gathered temperatures
+fn heat_round_trip(particles: &[(Vector3f, Float)]) -> (Float, Float, Vec<Float>) {
+  let mut world = specs::World::new();
+  world.register::<ParticleMass>();
+  world.register::<ParticlePosition>();
+  world.register::<ParticleTemperature>();
+  for &(position, temperature) in particles {
+    world
+      .create_entity()
+      .with(ParticleMass::new(2.0))
+      .with(ParticlePosition::new(position))
+      .with(ParticleTemperature::new(temperature, 3.0, 1.0))
+      .build();
+  }
+  world.insert(Grid::new(Vector3u::new(10, 10, 10), 0.1));
+  P2GHeatSystem.run_now(&world);
+
+  let mut grid = world.fetch_mut::<Grid>();
+  let mut grid_heat = 0.0;
+  for node in grid.nodes.iter_mut() {
+    grid_heat += node.heat_capacity * node.temperature_temp;
+    node.temperature = node.temperature_temp;
+  }
+  drop(grid);
+  G2PHeatSystem.run_now(&world);
+
+  let particle_heat = particles.iter().map(|&(_, t)| 2.0 * 3.0 * t).sum();
+  let temperatures = world.read_storage::<ParticleTemperature>();
+  (
+    particle_heat,
+    grid_heat,
+    temperatures.join().map(ParticleTemperature::get).collect(),
+  )
+}
+
+#[test]
+fn heat_transfer_round_trips() {
+  let particles: Vec<_> = (0..27)
+    .map(|i| {
+      let position = Vector3f::new(
+        0.3 + 0.13 * (i % 3) as Float,
+        0.35 + 0.11 * (i / 3 % 3) as Float,
+        0.4 + 0.07 * (i / 9) as Float,
+      );
+      (position, -5.0 + i as Float)
+    })
+    .collect();
+  let (particle_heat, grid_heat, temperatures) = heat_round_trip(&particles);
+
+  // The transfer conserves heat, and particles get their own temperatures back
+  assert!(
+    (particle_heat - grid_heat).abs() < 1e-3 * particle_heat.abs(),
+    "{} vs {}",
+    particle_heat,
+    grid_heat
+  );
+  for (&(_, expected), temperature) in particles.iter().zip(temperatures) {
+    assert!(
+      (temperature - expected).abs() < 1e-4,
+      "{} instead of {}",
+      temperature,
+      expected
+    );
+  }
+}
+
+#[test]
+fn uniform_temperature_stays_uniform() {
+  let particles: Vec<_> = (0..8)
+    .map(|i| {
+      (
+        Vector3f::new(0.42 + 0.05 * i as Float, 0.5, 0.47 + 0.02 * i as Float),
+        7.0,
+      )
+    })
+    .collect();
+  let world = {
+    let mut world = specs::World::new();
+    world.register::<ParticleMass>();
+    world.register::<ParticlePosition>();
+    world.register::<ParticleTemperature>();
+    for &(position, temperature) in &particles {
+      world
+        .create_entity()
+        .with(ParticleMass::new(1.0))
+        .with(ParticlePosition::new(position))
+        .with(ParticleTemperature::new(temperature, 1.0, 10.0))
+        .build();
+    }
+    world.insert(DeltaTime::default());
+    world.insert(Grid::new(Vector3u::new(10, 10, 10), 0.1));
+    world
+  };
+  P2GHeatSystem.run_now(&world);
+  GridHeatDiffusionSystem.run_now(&world);
+  G2PHeatSystem.run_now(&world);
+  for temperature in world.read_storage::<ParticleTemperature>().join() {
+    assert!((temperature.get() - 7.0).abs() < 1e-4, "Got {}", temperature.get());
+  }
+}
+
+/// A single melting particle, heated or cooled by `heat` each step
+fn phase_change(heat: Float, steps: usize, temperature: Float, latent: Float) -> Vec<(Float, Float)> {
+  let mut world = specs::World::new();
+  world.register::<ParticleTemperature>();
+  world.register::<ParticleMelting>();
+  world.register::<ParticleDeformation>();
+  let mut melting = ParticleMelting::new(0.0, 10.0);
+  melting.latent = latent;
+  world
+    .create_entity()
+    .with(ParticleTemperature::new(temperature, 2.0, 1.0))
+    .with(melting)
+    .with(ParticleDeformation::elastic(1000.0, 0.3))
+    .build();
+
+  let mut states = vec![];
+  for _ in 0..steps {
+    for temperature in (&mut world.write_storage::<ParticleTemperature>()).join() {
+      let t = temperature.get() + heat / temperature.heat_capacity;
+      temperature.set(t);
+    }
+    EvolvePhaseSystem.run_now(&world);
+    let (temperatures, deformations) = (
+      world.read_storage::<ParticleTemperature>(),
+      world.read_storage::<ParticleDeformation>(),
+    );
+    for (temperature, def) in (&temperatures, &deformations).join() {
+      states.push((temperature.get(), def.liquid_fraction));
+    }
+  }
+  states
+}
+
+#[test]
+fn latent_heat_holds_the_melting_point() {
+  // Heating by 1 each step reaches the melting point in 2 steps, then melts in 10 steps
+  let states = phase_change(1.0, 16, -1.0, 0.0);
+  for (step, &(temperature, liquid_fraction)) in states.iter().enumerate() {
+    let (expected_temperature, expected_fraction) = match step {
+      0 => (-0.5, 0.0),
+      1..=11 => (0.0, (step - 1) as Float / 10.0),
+      _ => ((step - 11) as Float * 0.5, 1.0),
+    };
+    assert!(
+      (temperature - expected_temperature).abs() < 1e-5 && (liquid_fraction - expected_fraction).abs() < 1e-5,
+      "Step {} at {} with a liquid fraction of {}",
+      step,
+      temperature,
+      liquid_fraction
+    );
+  }
+
+  // Cooling gives the latent heat back before the temperature drops
+  let states = phase_change(-1.0, 12, 0.0, 10.0);
+  for (step, &(temperature, liquid_fraction)) in states.iter().enumerate() {
+    let (expected_temperature, expected_fraction) = if step < 10 {
+      (0.0, 1.0 - (step + 1) as Float / 10.0)
+    } else {
+      ((step - 9) as Float * -0.5, 0.0)
+    };
+    assert!(
+      (temperature - expected_temperature).abs() < 1e-5 && (liquid_fraction - expected_fraction).abs() < 1e-5,
+      "Step {} at {} with a liquid fraction of {}",
+      step,
+      temperature,
+      liquid_fraction
+    );
+  }
+}
+
+#[test]
+#[should_panic]
+fn heat_capacity_must_be_positive() {
+  ParticleTemperature::new(0.0, 0.0, 1.0);
+}
+
+#[test]
+fn lame_parameters_follow_the_temperature() {
+  let mut world = specs::World::new();
+  world.register::<ParticleTemperature>();
+  world.register::<ParticleMelting>();
+  world.register::<ParticleDeformation>();
+  let reference = ParticleDeformation::elastic(1000.0, 0.3).with_thermal_softening(0.1, 20.0);
+  for &temperature in &[10.0, 20.0, 30.0] {
+    world
+      .create_entity()
+      .with(ParticleTemperature::new(temperature, 1.0, 1.0))
+      .with(reference)
+      .build();
+  }
+  EvolvePhaseSystem.run_now(&world);
+
+  let (mu_0, lambda_0) = reference.lame_parameters();
+  let (temperatures, deformations) = (
+    world.read_storage::<ParticleTemperature>(),
+    world.read_storage::<ParticleDeformation>(),
+  );
+  for (temperature, def) in (&temperatures, &deformations).join() {
+    let factor = (-0.1 * (temperature.get() - 20.0)).exp();
+    let (mu, lambda) = def.lame_parameters();
+    assert!(
+      (mu - factor * mu_0).abs() < 1e-3 * mu_0,
+      "mu {} at {}",
+      mu,
+      temperature.get()
+    );
+    assert!(
+      (lambda - factor * lambda_0).abs() < 1e-3 * lambda_0,
+      "lambda {} at {}",
+      lambda,
+      temperature.get()
+    );
+  }
+}
+
+/// Drop a block at the given temperature with a melting point of 0 onto a sticky floor, and
+/// return the height of its top after 0.1s
+fn slump(temperature: Float, viscosity: Float) -> Float {
+  let mut world = WorldBuilder::new()
+    .with_size_2d(Vector2f::new(0.6, 0.4))
+    .with_dx(0.04)
+    .with_dt(0.0005)
+    .build();
+  world.put_sticky_boundary(0.04);
+  world
+    .put_rectangle(Vector2f::new(0.24, 0.04), Vector2f::new(0.36, 0.2), 1e-3)
+    .with(ParticleDeformation::elastic(2000.0, 0.3))
+    .with(ParticleTemperature::new(temperature, 1.0, 1.0))
+    .with(ParticleMelting::new(0.0, 0.0).with_viscosity(viscosity));
+  for _ in 0..200 {
+    world.step();
+  }
+  let positions: ReadStorage<ParticlePosition> = world.world.system_data();
+  positions.join().map(|x| x.get().y).fold(0.0, Float::max)
+}
+
+#[test]
+fn melted_blocks_flow() {
+  // A solid block keeps its shape, a melted one slumps, and more slowly with a viscous melt
+  let solid = slump(-10.0, 0.0);
+  assert!(solid > 0.185, "The solid block went down to {}", solid);
+  let melted = slump(10.0, 0.0);
+  assert!(melted < 0.17, "The melted block stayed up to {}", melted);
+  let viscous = slump(10.0, 1.0);
+  assert!(viscous > 0.17, "The viscous melt went down to {}", viscous);
+}
@@ -0,0 +1,87 @@
+use mpm_rs::*;
+
+/// Check at random positions that the weights of a kernel sum up to 1, that the weight
+/// gradients sum up to 0, and that the weights reproduce linear functions:
+/// $\sum_i w_i x_i = x$ and $\sum_i \nabla w_i x_i = 1$
+fn check_kernel(kernel: &dyn Kernel) {
+  for _ in 0..1000 {
+    let x = 4.0 + 40.0 * random();
+    let (base_node, w, dw) = kernel.weights_1d(x);
+    let (mut sum_w, mut sum_dw, mut sum_wx, mut sum_dwx) = (0.0, 0.0, 0.0, 0.0);
+    for i in 0..kernel.support() {
+      let node = (base_node + i as i32) as Float;
+      sum_w += w[i];
+      sum_dw += dw[i];
+      sum_wx += w[i] * node;
+      sum_dwx += dw[i] * node;
+    }
+    assert!(
+      (sum_w - 1.0).abs() < 1e-5,
+      "{:?}: weights sum up to {} at {}",
+      kernel,
+      sum_w,
+      x
+    );
+    assert!(
+      sum_dw.abs() < 1e-5,
+      "{:?}: gradients sum up to {} at {}",
+      kernel,
+      sum_dw,
+      x
+    );
+    assert!(
+      (sum_wx - x).abs() < 1e-4,
+      "{:?}: reproduced {} at {}",
+      kernel,
+      sum_wx,
+      x
+    );
+    assert!(
+      (sum_dwx - 1.0).abs() < 1e-4,
+      "{:?}: reproduced gradient {} at {}",
+      kernel,
+      sum_dwx,
+      x
+    );
+  }
+}
+
+#[test]
+fn linear_kernel() {
+  check_kernel(&LinearKernel);
+}
+
+#[test]
+fn quadratic_kernel() {
+  check_kernel(&QuadraticKernel);
+}
+
+#[test]
+fn cubic_kernel() {
+  check_kernel(&CubicKernel);
+}
+
+#[test]
+fn gimp_kernel() {
+  for &half_size in &[0.0, 0.1, 0.25, 0.5] {
+    check_kernel(&GimpKernel::new(half_size));
+  }
+}
+
+#[test]
+fn kernel_weights_in_3d() {
+  // The tensor product keeps the partition of unity and the linear reproduction
+  let grid = Grid::new(Vector3u::new(20, 20, 20), 0.05).with_kernel(Box::new(CubicKernel));
+  for _ in 0..100 {
+    let pos = Vector3f::new(0.2 + 0.6 * random(), 0.2 + 0.6 * random(), 0.2 + 0.6 * random());
+    let (mut sum_w, mut sum_dw, mut sum_wx) = (0.0, Vector3f::zeros(), Vector3f::zeros());
+    for (node_index, w, dw) in grid.neighbor_weights(pos) {
+      sum_w += w;
+      sum_dw += dw;
+      sum_wx += w * grid.node_position(node_index);
+    }
+    assert!((sum_w - 1.0).abs() < 1e-5);
+    assert!(sum_dw.norm() < 1e-3);
+    assert!((sum_wx - pos).norm() < 1e-5);
+  }
+}
@@ -15,3 +15,208 @@ fn vector_point_transf_1() {
   let new_v = Math::vector_of_point(&new_p);
   println!("Vector3f of new Point3f: {}", new_v);
 }
+
+/// A random matrix with entries in [-1, 1)
+fn random_matrix() -> Matrix3f {
+  Matrix3f::from_fn(|_, _| 2.0 * random() - 1.0)
+}
+
+/// Check that $U$ and $V$ are rotations and that $U \Sigma V^T$ gives back $F$, with the
+/// singular values of nalgebra
+fn check_svd3(f: &Matrix3f) {
+  let tol = 1e-4 * f.norm().max(1e-30);
+  let (u, sigma, v) = Math::svd3(f);
+  for r in &[u, v] {
+    assert!(
+      (r.transpose() * r - Matrix3f::identity()).norm() < 1e-4,
+      "Not orthogonal {} for {}",
+      r,
+      f
+    );
+    assert!((r.determinant() - 1.0).abs() < 1e-4, "Not a rotation {} for {}", r, f);
+  }
+  let reconstructed = u * Matrix3f::from_diagonal(&sigma) * v.transpose();
+  assert!(
+    (reconstructed - f).norm() < tol,
+    "Wrong decomposition {} for {}",
+    reconstructed,
+    f
+  );
+
+  // Same singular values up to the sign of the smallest one, sorted by decreasing magnitude
+  let mut expected: Vec<_> = f.svd(false, false).singular_values.iter().cloned().collect();
+  expected.sort_by(|a, b| b.partial_cmp(a).unwrap());
+  for i in 0..3 {
+    assert!(
+      (sigma[i].abs() - expected[i]).abs() < tol,
+      "Wrong singular values {} for {}",
+      sigma,
+      f
+    );
+  }
+  assert!(
+    sigma[0] >= 0.0 && sigma[1] >= 0.0,
+    "Negative singular values {} for {}",
+    sigma,
+    f
+  );
+}
+
+#[test]
+fn svd3_random() {
+  for _ in 0..1000 {
+    check_svd3(&random_matrix());
+  }
+}
+
+#[test]
+fn svd3_scaled() {
+  for &scale in &[1e-6, 1e-3, 1e3, 1e6] {
+    for _ in 0..100 {
+      check_svd3(&(random_matrix() * scale));
+    }
+  }
+}
+
+#[test]
+fn svd3_degenerate() {
+  let a = random_matrix();
+  let rotation = Math::polar_rotation(&a);
+  let u = Vector3f::new(1.0, 2.0, 3.0);
+  let v = Vector3f::new(-1.0, 0.5, 2.0);
+  let cases = [
+    Matrix3f::zeros(),
+    Matrix3f::identity(),
+    -Matrix3f::identity(),
+    Matrix3f::from_diagonal(&Vector3f::new(1.0, 1.0, -1.0)),
+    Matrix3f::from_diagonal(&Vector3f::new(1.0, 1e-3, 1e-7)),
+    Matrix3f::from_diagonal(&Vector3f::new(0.0, 3.0, 0.0)),
+    u * v.transpose(),
+    u * v.transpose() + v * u.transpose(),
+    rotation * Matrix3f::from_diagonal(&Vector3f::new(2.0, 2.0, 1.0)),
+    rotation * Matrix3f::from_diagonal(&Vector3f::new(2.0, 2.0, 2.0)),
+    rotation,
+  ];
+  for f in &cases {
+    check_svd3(f);
+  }
+}
+
+#[test]
+fn svd3_nearly_singular() {
+  for _ in 0..100 {
+    let (r1, r2) = (
+      Math::polar_rotation(&random_matrix()),
+      Math::polar_rotation(&random_matrix()),
+    );
+    for &sigma in &[
+      Vector3f::new(1.0, 1e-3, 1e-6),
+      Vector3f::new(1.0, 1.0, 1e-6),
+      Vector3f::new(1.0, 1e-5, -1e-6),
+      Vector3f::new(1.0, 0.0, 0.0),
+    ] {
+      let f = r1 * Matrix3f::from_diagonal(&sigma) * r2.transpose();
+      let (u, computed, v) = Math::svd3(&f);
+      assert!((u.determinant() - 1.0).abs() < 1e-5, "Not a rotation {} for {}", u, f);
+      assert!((v.determinant() - 1.0).abs() < 1e-5, "Not a rotation {} for {}", v, f);
+      let reconstructed = u * Matrix3f::from_diagonal(&computed) * v.transpose();
+      assert!(
+        (reconstructed - f).norm() < 1e-5,
+        "Wrong decomposition {} for {}",
+        reconstructed,
+        f
+      );
+
+      // The small singular values are found to the precision of the largest one
+      assert!(
+        (computed - sigma).norm() < 1e-5,
+        "Got {} instead of {}",
+        computed,
+        sigma
+      );
+    }
+  }
+}
+
+#[test]
+fn svd3_sign() {
+  for _ in 0..1000 {
+    let f = random_matrix();
+    let (_, sigma, _) = Math::svd3(&f);
+    if f.determinant().abs() > 1e-3 {
+      assert_eq!(sigma[2] < 0.0, f.determinant() < 0.0, "Wrong sign {} for {}", sigma, f);
+    }
+  }
+}
+
+#[test]
+fn svd3_non_finite() {
+  // Garbage in, garbage out, but no panic
+  let (_, sigma, _) = Math::svd3(&Matrix3f::from_element(Float::NAN));
+  assert!(sigma.iter().all(|s| s.is_nan()));
+  Math::svd3(&Matrix3f::from_element(Float::INFINITY));
+}
+
+#[test]
+fn polar_rotation_random() {
+  for _ in 0..1000 {
+    let f = random_matrix() + Matrix3f::identity() * 2.0;
+    if f.determinant() < 0.1 {
+      continue;
+    }
+    let r = Math::polar_rotation(&f);
+    assert!((r.transpose() * r - Matrix3f::identity()).norm() < 1e-4);
+    assert!((r.determinant() - 1.0).abs() < 1e-4);
+
+    // The orthogonal polar factor of nalgebra, unique for invertible $F$ and a rotation when
+    // $det(F) > 0$. It is computed in double precision since close singular values make it
+    // inaccurate in single precision
+    let svd = f.map(|x| x as f64).svd(true, true);
+    let expected = svd.u.unwrap() * svd.v_t.unwrap();
+    let error = (r.map(|x| x as f64) - expected).norm();
+    assert!(error < 1e-4, "Wrong rotation {} for {}, expected {}", r, f, expected);
+
+    // The stretch $R^T F$ is symmetric
+    let s = r.transpose() * f;
+    assert!((s - s.transpose()).norm() < 1e-4 * f.norm());
+  }
+}
+
+#[test]
+fn cofactor_random() {
+  for _ in 0..100 {
+    let f = random_matrix() + Matrix3f::identity() * 2.0;
+    let expected = f.determinant() * f.try_inverse().unwrap().transpose();
+    assert!(
+      (Math::cofactor(&f) - expected).norm() < 1e-4 * expected.norm(),
+      "Wrong cofactor of {}",
+      f
+    );
+  }
+}
+
+#[test]
+fn cofactor_differential_random() {
+  let h = 1e-3;
+  for _ in 0..100 {
+    let (f, df) = (random_matrix(), random_matrix());
+    let expected = (Math::cofactor(&(f + h * df)) - Math::cofactor(&(f - h * df))) / (2.0 * h);
+    assert!((Math::cofactor_differential(&f, &df) - expected).norm() < 1e-2);
+  }
+}
+
+#[test]
+#[ignore]
+fn svd3_is_faster_than_nalgebra() {
+  // Timings only make sense in release builds, run with `cargo test --release -- --ignored`
+  let fs: Vec<_> = (0..100000).map(|_| random_matrix()).collect();
+  let start = std::time::Instant::now();
+  let ours: Float = fs.iter().map(|f| Math::svd3(f).1[0]).sum();
+  let ours_time = start.elapsed();
+  let start = std::time::Instant::now();
+  let theirs: Float = fs.iter().map(|f| f.svd(true, true).singular_values.max()).sum();
+  let theirs_time = start.elapsed();
+  println!("svd3 took {:?}, nalgebra took {:?}", ours_time, theirs_time);
+  assert!((ours - theirs).abs() < 1e-3 * theirs);
+  assert!(ours_time < theirs_time);
+}
@@ -0,0 +1,101 @@
+use mpm_rs::*;
+use specs::prelude::*;
+
+/// Let a block fall freely with the given pipeline and integrator, and return the mean velocity
+/// and the largest deviation of the deformation gradients from the identity
+fn free_fall(pipeline: Pipeline, integrator: Integrator, steps: usize) -> (Vector3f, Float) {
+  let mut world = WorldBuilder::new()
+    .with_size_2d(Vector2f::new(0.4, 0.4))
+    .with_dx(0.04)
+    .with_dt(0.001)
+    .with_pipeline(pipeline)
+    .with_integrator(integrator)
+    .build();
+  world
+    .put_rectangle(Vector2f::new(0.12, 0.2), Vector2f::new(0.28, 0.32), 1.0)
+    .with(ParticleDeformation::elastic(10000.0, 0.3));
+  for _ in 0..steps {
+    world.step();
+  }
+
+  let (velocities, deformations): (ReadStorage<ParticleVelocity>, ReadStorage<ParticleDeformation>) =
+    world.world.system_data();
+  let (mut sum, mut count, mut deviation) = (Vector3f::zeros(), 0.0, 0.0);
+  for (v, def) in (&velocities, &deformations).join() {
+    sum += v.get();
+    count += 1.0;
+    deviation = Float::max(deviation, (def.f_elastic - Matrix3f::identity()).norm());
+  }
+  (sum / count, deviation)
+}
+
+#[test]
+fn pipelines_fall_freely() {
+  let steps = 20;
+  let expected = Vector3f::new(0.0, -9.8 * 0.001 * steps as Float, 0.0);
+  for &(pipeline, integrator) in &[
+    (Pipeline::Standard, Integrator::Explicit),
+    (Pipeline::MlsMpm, Integrator::Explicit),
+    (Pipeline::Standard, Integrator::implicit()),
+  ] {
+    let (velocity, deviation) = free_fall(pipeline, integrator, steps);
+    assert!(
+      (velocity - expected).norm() < 1e-3,
+      "{:?} {:?} falls at {}",
+      pipeline,
+      integrator,
+      velocity
+    );
+    assert!(
+      deviation < 1e-3,
+      "{:?} {:?} deformed by {}",
+      pipeline,
+      integrator,
+      deviation
+    );
+  }
+}
+
+/// Drop a stiff block onto a sticky floor, and return the largest particle speed during the steps,
+/// which goes above 10 when the integration diverges
+fn stiff_block(integrator: Integrator, dt: Float) -> Float {
+  let mut world = WorldBuilder::new()
+    .with_size_2d(Vector2f::new(0.4, 0.4))
+    .with_dx(0.04)
+    .with_dt(dt)
+    .with_integrator(integrator)
+    .build();
+  world.put_sticky_boundary(0.08);
+  world
+    .put_rectangle(Vector2f::new(0.12, 0.08), Vector2f::new(0.28, 0.2), 1.0)
+    .with(ParticleDeformation::elastic(2e5, 0.3));
+  let mut max_speed = 0.0;
+  for _ in 0..40 {
+    world.step();
+    let velocities: ReadStorage<ParticleVelocity> = world.world.system_data();
+    for v in velocities.join() {
+      let speed = v.get().norm();
+      max_speed = if speed.is_finite() {
+        Float::max(max_speed, speed)
+      } else {
+        Float::INFINITY
+      };
+    }
+
+    // Stop before a diverging block leaves the grid
+    if max_speed > 10.0 {
+      break;
+    }
+  }
+  max_speed
+}
+
+#[test]
+fn implicit_is_stable_with_stiff_materials() {
+  // The time step is well beyond the limit of the explicit integrator for this stiffness
+  let dt = 0.005;
+  let explicit = stiff_block(Integrator::Explicit, dt);
+  assert!(explicit > 10.0, "Explicit integration stayed at {}", explicit);
+  let implicit = stiff_block(Integrator::implicit(), dt);
+  assert!(implicit < 1.0, "Implicit integration reached {}", implicit);
+}
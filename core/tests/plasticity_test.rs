@@ -0,0 +1,212 @@
+use mpm_rs::*;
+use specs::prelude::*;
+
+const MU: Float = 1.0;
+const LAMBDA: Float = 1.5;
+
+fn random_sigma() -> Vector3f {
+  Vector3f::new(0.5 + random(), 0.5 + random(), 0.5 + random())
+}
+
+/// The value of the Drucker-Prager yield function of the singular values, which is at most 0
+/// inside of the cone
+fn drucker_prager_yield(model: &DruckerPrager, sigma: &Vector3f) -> Float {
+  let eps = sigma.map(Float::ln);
+  let trace = eps.sum();
+  let dev = eps.add_scalar(-trace / 3.0);
+  dev.norm() + (3.0 * LAMBDA + 2.0 * MU) / (2.0 * MU) * trace * model.alpha()
+}
+
+#[test]
+fn drucker_prager_projects_into_the_cone() {
+  let model = DruckerPrager::new(30.0).with_volume_correction(false);
+  for _ in 0..1000 {
+    let mut state = 0.0;
+    let sigma = model.project(&random_sigma(), MU, LAMBDA, &mut state);
+    assert!(sigma.map(Float::ln).sum() < 1e-5, "Projected to {} with tension", sigma);
+    assert!(
+      drucker_prager_yield(&model, &sigma) < 1e-4,
+      "Projected to {} outside of the cone",
+      sigma
+    );
+  }
+}
+
+#[test]
+fn drucker_prager_keeps_elastic_states() {
+  let model = DruckerPrager::new(30.0);
+  for _ in 0..1000 {
+    let sigma = random_sigma();
+    let mut state = 0.0;
+    let projected = model.project(&sigma, MU, LAMBDA, &mut state);
+    if sigma.map(Float::ln).sum() < 0.0 && drucker_prager_yield(&model, &sigma) < 0.0 {
+      assert!(
+        (projected - sigma).norm() < 1e-5,
+        "Projected {} inside of the cone to {}",
+        sigma,
+        projected
+      );
+      assert_eq!(state, 0.0);
+    }
+  }
+}
+
+#[test]
+fn drucker_prager_remembers_separation() {
+  let model = DruckerPrager::new(30.0);
+  let mut state = 0.0;
+  let sigma = model.project(&Vector3f::new(1.1, 1.0, 1.0), MU, LAMBDA, &mut state);
+  assert!((sigma - Vector3f::repeat(1.0)).norm() < 1e-6);
+  assert!((state - (1.1 as Float).ln()).abs() < 1e-6);
+
+  // The gained volume is given back under compression
+  let sigma = model.project(&Vector3f::repeat(0.95), MU, LAMBDA, &mut state);
+  assert!((sigma - Vector3f::repeat(0.95 * (1.1 as Float).cbrt())).norm() < 1e-5);
+  assert_eq!(state, 0.0);
+}
+
+/// The deviatoric and volumetric parts of the Hencky strain of the singular values
+fn hencky_strain(sigma: &Vector3f) -> (Vector3f, Float) {
+  let eps = sigma.map(Float::ln);
+  let trace = eps.sum();
+  (eps.add_scalar(-trace / 3.0), trace)
+}
+
+#[test]
+fn von_mises_projects_onto_the_yield_surface() {
+  let model = VonMises::new(0.1).with_hardening(0.5);
+  for _ in 0..1000 {
+    let (sigma, mut state) = (random_sigma(), 0.2 * random());
+    let old_state = state;
+    let projected = model.project(&sigma, MU, LAMBDA, &mut state);
+    let radius = (2.0 / 3.0 as Float).sqrt() * (model.yield_stress + model.hardening_modulus * state);
+    let (dev, trace) = hencky_strain(&projected);
+    assert!(
+      2.0 * MU * dev.norm() < radius + 1e-4,
+      "Projected to {} outside of the yield surface",
+      projected
+    );
+    assert!(state >= old_state);
+
+    // The return mapping is purely deviatoric
+    assert!(
+      (trace - hencky_strain(&sigma).1).abs() < 1e-5,
+      "Volume changed from {} to {}",
+      sigma,
+      projected
+    );
+  }
+}
+
+#[test]
+fn von_mises_keeps_elastic_states() {
+  let model = VonMises::new(0.1).with_hardening(0.5);
+  for _ in 0..1000 {
+    let sigma = Vector3f::repeat(0.9) + 0.05 * random_sigma();
+    let mut state = 0.0;
+    let projected = model.project(&sigma, MU, LAMBDA, &mut state);
+    let (dev, _) = hencky_strain(&sigma);
+    if 2.0 * MU * dev.norm() < (2.0 / 3.0 as Float).sqrt() * model.yield_stress {
+      assert_eq!(projected, sigma);
+      assert_eq!(state, 0.0);
+    }
+  }
+}
+
+/// The value of the Cam-Clay yield function given the hardened $p_0$, which is at most 0 inside
+/// of the ellipse
+fn cam_clay_yield(model: &CamClay, sigma: &Vector3f, p0: Float) -> Float {
+  let kappa = LAMBDA + 2.0 / 3.0 * MU;
+  let (dev, trace) = hencky_strain(sigma);
+  let (p, q) = (-kappa * trace, (1.5 as Float).sqrt() * 2.0 * MU * dev.norm());
+  (1.0 + 2.0 * model.beta) * q * q + model.m * model.m * (p + model.beta * p0) * (p - p0)
+}
+
+#[test]
+fn cam_clay_projects_into_the_ellipse() {
+  let model = CamClay::new(1.2, 0.3, 5.0);
+  let kappa = LAMBDA + 2.0 / 3.0 * MU;
+  for _ in 0..1000 {
+    let sigma = random_sigma();
+    let mut state = -0.1;
+    let p0 = kappa * (1e-5 + (model.hardening * 0.1).sinh());
+    let projected = model.project(&sigma, MU, LAMBDA, &mut state);
+    let value = cam_clay_yield(&model, &projected, p0);
+    assert!(
+      value < 1e-4,
+      "Projected {} to {} outside of the ellipse",
+      sigma,
+      projected
+    );
+    if cam_clay_yield(&model, &sigma, p0) <= 0.0 {
+      assert_eq!(projected, sigma);
+      assert_eq!(state, -0.1);
+    }
+  }
+}
+
+/// Let a 45 degree wedge of sand slump onto a sticky floor, and return the slope of its sides
+/// in degrees, fitted between 20% and 80% of its height
+fn angle_of_repose(friction_angle: Float) -> Float {
+  let (dx, center, half_width) = (0.02, 0.4, 0.25);
+  let mut world = WorldBuilder::new()
+    .with_size_2d(Vector2f::new(0.8, 0.4))
+    .with_dx(dx)
+    .with_dt(0.0005)
+    .with_transfer_scheme(TransferScheme::Apic)
+    .build();
+  world.put_sticky_boundary(2.0 * dx);
+  let floor = 2.0 * dx;
+  let wedge = [
+    Vector2f::new(center - half_width, floor),
+    Vector2f::new(center + half_width, floor),
+    Vector2f::new(center, floor + half_width),
+  ];
+  world
+    .put_polygon(&wedge, 1.0)
+    .with(ParticleDeformation::elastic(20000.0, 0.3))
+    .with(ParticleConstitutiveModel::new(Hencky))
+    .with(ParticlePlasticity::drucker_prager(friction_angle));
+  for _ in 0..800 {
+    world.step();
+  }
+
+  // The height of the pile in each column of cells
+  let positions: ReadStorage<ParticlePosition> = world.world.system_data();
+  let mut heights = vec![0.0; (2.0 * center / dx) as usize];
+  for x in positions.join().map(|x| x.get()) {
+    let column = (x.x / dx) as usize;
+    heights[column] = Float::max(heights[column], x.y - floor);
+  }
+  let top = heights.iter().cloned().fold(0.0, Float::max);
+
+  // Least squares fit of the height against the distance to the center
+  let samples: Vec<(Float, Float)> = heights
+    .iter()
+    .enumerate()
+    .filter(|(_, &h)| h > 0.2 * top && h < 0.8 * top)
+    .map(|(i, &h)| (((i as Float + 0.5) * dx - center).abs(), h))
+    .collect();
+  let n = samples.len() as Float;
+  let (sx, sy) = samples.iter().fold((0.0, 0.0), |(sx, sy), (r, h)| (sx + r, sy + h));
+  let (sxx, sxy) = samples
+    .iter()
+    .fold((0.0, 0.0), |(sxx, sxy), (r, h)| (sxx + r * r, sxy + r * h));
+  let slope = -(n * sxy - sx * sy) / (n * sxx - sx * sx);
+  slope.atan().to_degrees()
+}
+
+#[test]
+#[ignore]
+fn drucker_prager_angle_of_repose() {
+  // Takes minutes in debug builds, run with `cargo test --release -- --ignored`
+  for &friction_angle in &[20.0, 35.0] {
+    let angle = angle_of_repose(friction_angle);
+    assert!(
+      (angle - friction_angle).abs() < 6.0,
+      "Friction angle {} gave an angle of repose of {}",
+      friction_angle,
+      angle
+    );
+  }
+}
@@ -0,0 +1,130 @@
+use mpm_rs::*;
+use specs::prelude::*;
+
+fn rigid_world(floor: bool) -> mpm_rs::World<'static, 'static> {
+  let mut world = WorldBuilder::new()
+    .with_size_2d(Vector2f::new(0.6, 0.4))
+    .with_dx(0.04)
+    .with_dt(0.001)
+    .with_transfer_scheme(TransferScheme::Apic)
+    .build();
+  if floor {
+    world.put_sticky_boundary(0.04);
+  }
+  world
+}
+
+/// Put a rigid rectangle sampled on a regular lattice in the plane of the world
+fn put_rigid_rectangle(
+  world: &mut mpm_rs::World<'static, 'static>,
+  min: Vector2f,
+  max: Vector2f,
+  mass: Float,
+  velocity: Vector3f,
+) -> Entity {
+  let z = world.world.fetch::<Grid>().plane_z();
+  let (nx, ny) = (
+    ((max.x - min.x) / 0.02).round() as usize,
+    ((max.y - min.y) / 0.02).round() as usize,
+  );
+  let mut samples = vec![];
+  for i in 0..=nx {
+    for j in 0..=ny {
+      samples.push(Vector3f::new(min.x + 0.02 * i as Float, min.y + 0.02 * j as Float, z));
+    }
+  }
+  let body = RigidBody::new(&samples, mass, UnitQuaternionf::identity()).with_velocity(velocity);
+  world.world.create_entity().with(body).build()
+}
+
+fn rigid_body(world: &mpm_rs::World<'static, 'static>, entity: Entity) -> RigidBody {
+  world.world.read_storage::<RigidBody>().get(entity).unwrap().clone()
+}
+
+#[test]
+fn rigid_body_falls_freely() {
+  let mut world = rigid_world(false);
+  let entity = put_rigid_rectangle(
+    &mut world,
+    Vector2f::new(0.24, 0.2),
+    Vector2f::new(0.32, 0.28),
+    1.0,
+    Vector3f::zeros(),
+  );
+  let steps = 20;
+  for _ in 0..steps {
+    world.step();
+  }
+
+  let body = rigid_body(&world, entity);
+  let t = 0.001 * steps as Float;
+  let expected_velocity = Vector3f::new(0.0, -9.8 * t, 0.0);
+  assert!(
+    (body.velocity - expected_velocity).norm() < 1e-3,
+    "Falls at {}",
+    body.velocity
+  );
+  assert!(
+    (body.position.y - (0.24 - 9.8 * 0.001 * t * (steps + 1) as Float / 2.0)).abs() < 1e-4,
+    "Fell to {}",
+    body.position.y
+  );
+  assert!(
+    body.angular_velocity.norm() < 1e-3,
+    "Spins at {}",
+    body.angular_velocity
+  );
+}
+
+#[test]
+fn rigid_body_exchanges_momentum_with_particles() {
+  let mut world = rigid_world(false);
+  world
+    .put_rectangle(Vector2f::new(0.3, 0.16), Vector2f::new(0.42, 0.28), 1.0)
+    .with(ParticleDeformation::elastic(20000.0, 0.3));
+  let entity = put_rigid_rectangle(
+    &mut world,
+    Vector2f::new(0.16, 0.18),
+    Vector2f::new(0.26, 0.26),
+    1.0,
+    Vector3f::new(2.0, 0.0, 0.0),
+  );
+  for _ in 0..40 {
+    world.step();
+  }
+
+  // The body pushes the block, and the momentum along x is conserved
+  let body = rigid_body(&world, entity);
+  let (masses, velocities): (ReadStorage<ParticleMass>, ReadStorage<ParticleVelocity>) = world.world.system_data();
+  let block: Float = (&masses, &velocities).join().map(|(m, v)| m.get() * v.get().x).sum();
+  assert!(body.velocity.x < 1.5, "The body kept going at {}", body.velocity.x);
+  assert!(block > 0.5, "The block only got a momentum of {}", block);
+  let total = block + body.mass * body.velocity.x;
+  assert!((total - 2.0).abs() < 1e-3, "The momentum went from 2 to {}", total);
+}
+
+#[test]
+fn wall_stops_rigid_body() {
+  let mut world = rigid_world(true);
+  let entity = put_rigid_rectangle(
+    &mut world,
+    Vector2f::new(0.24, 0.12),
+    Vector2f::new(0.32, 0.2),
+    1.0,
+    Vector3f::zeros(),
+  );
+  for _ in 0..300 {
+    world.step();
+  }
+
+  // Free falling, the body would be at 3 m/s by now
+  let body = rigid_body(&world, entity);
+  assert!(body.velocity.norm() < 0.1, "Still moving at {}", body.velocity);
+  assert!(body.position.y > 0.04, "Went through the wall to {}", body.position.y);
+}
+
+#[test]
+#[should_panic]
+fn rigid_body_needs_samples() {
+  RigidBody::new(&[], 1.0, UnitQuaternionf::identity());
+}
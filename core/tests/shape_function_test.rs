@@ -0,0 +1,62 @@
+use mpm_rs::*;
+
+fn random_position() -> Vector3f {
+  Vector3f::new(0.3 + 0.4 * random(), 0.3 + 0.4 * random(), 0.3 + 0.4 * random())
+}
+
+/// Check that the weights of a particle sum up to 1, that the gradients sum up to 0, and that
+/// they reproduce linear functions. Returns the number of neighbor nodes
+fn check_weights(grid: &Grid, pos: Vector3f, volume: Float, f: &Matrix3f) -> usize {
+  let (mut count, mut sum_w, mut sum_dw) = (0, 0.0, Vector3f::zeros());
+  let (mut sum_wx, mut sum_dwx) = (Vector3f::zeros(), Matrix3f::zeros());
+  for (node_index, w, dw) in grid.particle_weights(pos, volume, f) {
+    let x = grid.node_position(node_index);
+    count += 1;
+    sum_w += w;
+    sum_dw += dw;
+    sum_wx += w * x;
+    sum_dwx += dw * x.transpose();
+  }
+  assert!((sum_w - 1.0).abs() < 1e-4, "Weights sum up to {} for {}", sum_w, f);
+  assert!(sum_dw.norm() < 1e-2, "Gradients sum up to {} for {}", sum_dw, f);
+  assert!((sum_wx - pos).norm() < 1e-5, "Reproduced {} instead of {}", sum_wx, pos);
+  assert!(
+    (sum_dwx - Matrix3f::identity()).norm() < 1e-3,
+    "Reproduced gradient {}",
+    sum_dwx
+  );
+  count
+}
+
+#[test]
+fn gimp_weights() {
+  let grid = Grid::new(Vector3u::new(20, 20, 20), 0.05).with_shape_function(ShapeFunction::Gimp);
+  for &volume in &[0.0, 1e-6, 0.025 * 0.025 * 0.025, 0.05 * 0.05 * 0.05] {
+    for _ in 0..100 {
+      check_weights(&grid, random_position(), volume, &Matrix3f::identity());
+    }
+  }
+}
+
+#[test]
+fn cpdi_weights() {
+  let grid = Grid::new(Vector3u::new(20, 20, 20), 0.05).with_shape_function(ShapeFunction::Cpdi);
+  let volume = 0.025 * 0.025 * 0.025;
+  for _ in 0..100 {
+    let f = Matrix3f::identity() + Matrix3f::from_fn(|_, _| random() - 0.5);
+    check_weights(&grid, random_position(), volume, &f);
+  }
+}
+
+#[test]
+fn cpdi_weights_degenerate_domain() {
+  // Stretched and collapsed domains fall back to GIMP, with at most 3 nodes along each axis
+  let grid = Grid::new(Vector3u::new(20, 20, 20), 0.05).with_shape_function(ShapeFunction::Cpdi);
+  let volume = 0.025 * 0.025 * 0.025;
+  let stretched = Matrix3f::from_diagonal(&Vector3f::new(1000.0, 1.0, 1.0));
+  let sheared = Matrix3f::new(1.0, 1e6, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0);
+  let flat = Matrix3f::from_diagonal(&Vector3f::new(1.0, 1.0, 0.0));
+  for f in &[stretched, sheared, flat, Matrix3f::zeros()] {
+    assert!(check_weights(&grid, random_position(), volume, f) <= 27);
+  }
+}
@@ -0,0 +1,116 @@
+use mpm_rs::*;
+use specs::prelude::*;
+
+fn snow(hardening: Float) -> ParticleDeformation {
+  ParticleDeformation::new(140000.0, 0.2, 0.025, 0.0075, hardening)
+}
+
+/// Smash a slab of snow into a sticky floor and return the mean plastic volume ratio J_p
+fn pack_snow(hardening: Float) -> Float {
+  let mut world = WorldBuilder::new()
+    .with_size_2d(Vector2f::new(0.4, 0.4))
+    .with_dx(0.04)
+    .with_dt(0.0005)
+    .build();
+  world.put_sticky_boundary(0.08);
+  world
+    .put_rectangle(Vector2f::new(0.12, 0.06), Vector2f::new(0.28, 0.22), 0.4)
+    .with(ParticleVelocity::new(Vector3f::new(0.0, -4.0, 0.0)))
+    .with(snow(hardening));
+  for _ in 0..40 {
+    world.step();
+  }
+
+  let deformations: ReadStorage<ParticleDeformation> = world.world.system_data();
+  let (sum, count) = deformations.join().fold((0.0, 0.0), |(sum, count), def| {
+    let j_p = def.f_plastic.determinant();
+    assert!(j_p.is_finite() && j_p > 0.0);
+    (sum + j_p, count + 1.0)
+  });
+  sum / count
+}
+
+/// Pull the two halves of a slab apart and return the portion of the particles in the largest
+/// chunk, where particles closer than dx are connected
+fn tear(def: ParticleDeformation) -> Float {
+  let dx = 0.04;
+  let mut world = WorldBuilder::new()
+    .with_size_2d(Vector2f::new(0.6, 0.4))
+    .with_dx(dx)
+    .with_dt(0.0005)
+    .build();
+  world.put_sliding_boundary(0.08);
+  world
+    .put_rectangle(Vector2f::new(0.2, 0.16), Vector2f::new(0.4, 0.28), 1.0)
+    .with(def);
+  {
+    let (positions, mut velocities): (ReadStorage<ParticlePosition>, WriteStorage<ParticleVelocity>) =
+      world.world.system_data();
+    for (x, v) in (&positions, &mut velocities).join() {
+      let dir = if x.get().x < 0.3 { -1.0 } else { 1.0 };
+      v.set(Vector3f::new(4.0 * dir, 0.0, 0.0));
+    }
+  }
+  for _ in 0..60 {
+    world.step();
+  }
+
+  let positions: ReadStorage<ParticlePosition> = world.world.system_data();
+  let xs: Vec<Vector3f> = positions.join().map(|x| x.get()).collect();
+  let (mut visited, mut largest) = (vec![false; xs.len()], 0);
+  for start in 0..xs.len() {
+    if visited[start] {
+      continue;
+    }
+    let (mut stack, mut size) = (vec![start], 0);
+    visited[start] = true;
+    while let Some(i) = stack.pop() {
+      size += 1;
+      for j in 0..xs.len() {
+        if !visited[j] && (xs[i] - xs[j]).norm() < dx {
+          visited[j] = true;
+          stack.push(j);
+        }
+      }
+    }
+    largest = largest.max(size);
+  }
+  largest as Float / xs.len() as Float
+}
+
+#[test]
+fn hardening_factor_under_compression() {
+  let mut def = snow(10.0);
+  assert_eq!(def.hardening_factor(), 1.0);
+
+  def.f_plastic = Matrix3f::identity() * 0.9;
+  let (mu, lambda) = def.lame_parameters();
+  assert!(def.hardening_factor() > 1.0);
+  assert!((mu / def.mu - def.hardening_factor()).abs() < 1e-5);
+  assert!((lambda / def.lambda - def.hardening_factor()).abs() < 1e-5);
+}
+
+#[test]
+fn hardening_factor_degenerate_plastic() {
+  let mut def = snow(10.0);
+  def.f_plastic = Matrix3f::zeros();
+  assert_eq!(def.hardening_factor(), 1.0);
+  def.f_plastic = Matrix3f::from_element(Float::NAN);
+  assert_eq!(def.hardening_factor(), 1.0);
+}
+
+#[test]
+fn hardening_resists_packing() {
+  let soft = pack_snow(0.0);
+  let hard = pack_snow(10.0);
+  assert!(hard > soft + 0.05, "soft {} hard {}", soft, hard);
+}
+
+#[test]
+fn snow_breaks_into_chunks() {
+  // Snow yields under a small stretch and tears apart, while an elastic slab holds together
+  let elastic = ParticleDeformation::new(140000.0, 0.2, 10.0, 10.0, 0.0);
+  let (snow, elastic) = (tear(snow(10.0)), tear(elastic));
+  assert!(snow < 0.75, "The largest chunk of snow has {} of the particles", snow);
+  assert_eq!(elastic, 1.0);
+}
@@ -0,0 +1,116 @@
+use mpm_rs::*;
+use specs::prelude::*;
+
+const DX: Float = 0.02;
+const MIN_DT: Float = 1e-6;
+const MAX_DT: Float = 1e-2;
+
+/// A world with a single particle moving at `velocity`, ready for `AdaptDeltaTimeSystem`
+fn adaptive_world(velocity: Vector3f, def: Option<ParticleDeformation>) -> specs::World {
+  let mut world = specs::World::new();
+  world.register::<ParticleVelocity>();
+  world.register::<ParticleMass>();
+  world.register::<ParticleVolume>();
+  world.register::<ParticleDeformation>();
+  world.register::<ParticleFluid>();
+  world.insert(TimeStep::adaptive(MIN_DT, MAX_DT));
+  world.insert(Grid::new(Vector3u::new(10, 10, 10), DX));
+  world.insert(DeltaTime::default());
+  let builder = world
+    .create_entity()
+    .with(ParticleVelocity::new(velocity))
+    .with(ParticleMass::new(2e-3))
+    .with(ParticleVolume::new(1e-6));
+  match def {
+    Some(def) => builder.with(def).build(),
+    None => builder.build(),
+  };
+  world
+}
+
+fn adapted_dt(world: &specs::World) -> Float {
+  AdaptDeltaTimeSystem.run_now(world);
+  world.fetch::<DeltaTime>().get()
+}
+
+#[test]
+fn adaptive_dt_follows_cfl() {
+  let velocity = Vector3f::new(3.0, -4.0, 0.0);
+  let def = ParticleDeformation::elastic(1e4, 0.3);
+  let (mu, lambda) = def.lame_parameters();
+  let wave_speed = ((lambda + 2.0 * mu) * 1e-6 / 2e-3 as Float).sqrt();
+  let expected = 0.5 * DX / (5.0 + wave_speed);
+  let dt = adapted_dt(&adaptive_world(velocity, Some(def)));
+  assert!(
+    (dt - expected).abs() < 1e-6 * expected,
+    "dt {} instead of {}",
+    dt,
+    expected
+  );
+
+  // Without a wave speed, only the velocity counts
+  let dt = adapted_dt(&adaptive_world(velocity, None));
+  assert!((dt - 0.5 * DX / 5.0).abs() < 1e-8);
+}
+
+#[test]
+fn adaptive_dt_is_clamped() {
+  let dt = adapted_dt(&adaptive_world(Vector3f::new(1e6, 0.0, 0.0), None));
+  assert_eq!(dt, MIN_DT);
+  let dt = adapted_dt(&adaptive_world(Vector3f::new(1e-3, 0.0, 0.0), None));
+  assert_eq!(dt, MAX_DT);
+  let dt = adapted_dt(&adaptive_world(Vector3f::zeros(), None));
+  assert_eq!(dt, MAX_DT);
+}
+
+#[test]
+fn adaptive_dt_falls_back_on_blown_up_particles() {
+  for &speed in &[Float::INFINITY, Float::NAN] {
+    let dt = adapted_dt(&adaptive_world(Vector3f::new(speed, 0.0, 0.0), None));
+    assert_eq!(dt, MIN_DT);
+  }
+}
+
+fn falling_particle(time_step: TimeStep) -> mpm_rs::World<'static, 'static> {
+  let mut world = WorldBuilder::new()
+    .with_size(Vector3f::new(0.2, 0.2, 0.2))
+    .with_dx(0.02)
+    .with_dt(0.003)
+    .with_time_step(time_step)
+    .build();
+  world.put_particle(Vector3f::new(0.1, 0.1, 0.1), 0.001);
+  world
+}
+
+#[test]
+fn fixed_dt_lands_on_frames() {
+  let mut world = falling_particle(TimeStep::Fixed);
+
+  // Steps of 0.003, 0.003, then the remaining 0.004 split in two, or in three with rounding errors
+  let steps = world.advance_frame(0.01);
+  assert!((4..=5).contains(&steps), "Frame took {} steps", steps);
+  assert!((world.time() - 0.01).abs() < 1e-6);
+  assert_eq!(world.dt(), 0.003);
+  world.advance_frame(0.01);
+  assert!((world.time() - 0.02).abs() < 1e-6);
+  assert_eq!(world.world.fetch::<SimulationTime>().frame(), 2);
+}
+
+#[test]
+fn adaptive_dt_lands_on_frames() {
+  let mut world = falling_particle(TimeStep::adaptive(1e-5, 0.003));
+  for frame in 1..4 {
+    world.advance_frame(0.01);
+    assert!((world.time() - 0.01 * frame as Float).abs() < 1e-6);
+    assert!(world.dt() <= 0.003);
+    let time = world.world.fetch::<SimulationTime>();
+    assert!(time.is_frame_end());
+    assert_eq!(time.frame(), frame);
+  }
+}
+
+#[test]
+#[should_panic]
+fn frame_must_be_positive() {
+  falling_particle(TimeStep::Fixed).advance_frame(0.0);
+}
@@ -0,0 +1,144 @@
+use mpm_rs::*;
+use specs::prelude::*;
+
+/// The cross product matrix $[\omega]_\times$, i.e. the velocity gradient of a rigid rotation
+fn skew(w: Vector3f) -> Matrix3f {
+  Matrix3f::new(0.0, -w.z, w.y, w.z, 0.0, -w.x, -w.y, w.x, 0.0)
+}
+
+/// The angular momentum around z of the particles around their center of mass, which gravity
+/// does not change. With APIC, the affine velocities also carry the angular momentum
+/// $m_p D_p (C_{p,yx} - C_{p,xy})$
+fn angular_momentum(world: &mpm_rs::World, apic: bool) -> Float {
+  let (masses, positions, velocities, affines): (
+    ReadStorage<ParticleMass>,
+    ReadStorage<ParticlePosition>,
+    ReadStorage<ParticleVelocity>,
+    ReadStorage<ParticleAffineVelocity>,
+  ) = world.world.system_data();
+  let inertia = 1.0 / world.world.fetch::<Grid>().apic_inverse_inertia().unwrap();
+  let particles: Vec<_> = (&masses, &positions, &velocities, &affines)
+    .join()
+    .map(|(m, x, v, c)| (m.get(), x.get(), v.get(), c.get()))
+    .collect();
+  let mass: Float = particles.iter().map(|p| p.0).sum();
+  let center = particles.iter().fold(Vector3f::zeros(), |acc, p| acc + p.0 * p.1) / mass;
+  let velocity = particles.iter().fold(Vector3f::zeros(), |acc, p| acc + p.0 * p.2) / mass;
+  particles
+    .iter()
+    .map(|(m, x, v, c)| {
+      let affine = if apic { inertia * (c[(1, 0)] - c[(0, 1)]) } else { 0.0 };
+      m * ((x - center).cross(&(v - velocity)).z + affine)
+    })
+    .sum()
+}
+
+/// Spin a block of free particles around z for `num_steps` and return the relative change of
+/// its angular momentum
+fn spin(scheme: TransferScheme, num_steps: usize) -> Float {
+  let apic = scheme == TransferScheme::Apic;
+  let mut world = WorldBuilder::new()
+    .with_size(Vector3f::new(0.5, 0.5, 0.5))
+    .with_dx(0.05)
+    .with_dt(0.001)
+    .with_transfer_scheme(scheme)
+    .build();
+  let (center, omega, h) = (Vector3f::new(0.25, 0.25, 0.25), Vector3f::new(0.0, 0.0, 5.0), 0.025);
+  for i in 0..6 {
+    for j in 0..6 {
+      for k in 0..6 {
+        let x = center + (Vector3f::new(i as Float, j as Float, k as Float) - Vector3f::repeat(2.5)) * h;
+        world
+          .put_particle(x, 0.002)
+          .with(ParticleVelocity::new(omega.cross(&(x - center))))
+          .with(ParticleAffineVelocity::new(skew(omega)));
+      }
+    }
+  }
+
+  let initial = angular_momentum(&world, apic);
+  for _ in 0..num_steps {
+    world.step();
+  }
+  (angular_momentum(&world, apic) - initial).abs() / initial
+}
+
+#[test]
+fn apic_keeps_angular_momentum() {
+  let apic = spin(TransferScheme::Apic, 40);
+  let pic_flip = spin(TransferScheme::PicFlip, 40);
+  assert!(apic < 1e-4, "APIC changed the angular momentum by {}", apic);
+  assert!(pic_flip > 10.0 * apic, "APIC {} PIC/FLIP {}", apic, pic_flip);
+}
+
+/// The velocities after G2P of particles moving at `velocity` on a grid whose nodes all go from
+/// `before` to `after`, for each of the given per particle flip ratios
+fn gather_velocities(velocity: Vector3f, before: Vector3f, after: Vector3f, ratios: &[Option<Float>]) -> Vec<Vector3f> {
+  let mut world = specs::World::new();
+  world.register::<ParticleVelocity>();
+  world.register::<ParticlePosition>();
+  world.register::<ParticleAffineVelocity>();
+  world.register::<ParticleFlipRatio>();
+  world.register::<ParticleVolume>();
+  world.register::<ParticleDeformation>();
+  world.register::<ParticleBody>();
+  world.insert(DeltaTime::default());
+  world.insert(TransferScheme::PicFlip);
+  world.insert(FlipRatio::default());
+
+  let mut grid = Grid::new(Vector3u::new(8, 8, 8), 0.1);
+  for node_index in grid.indices() {
+    let node = grid.get_node_mut(node_index);
+    node.velocity_temp = before;
+    node.velocity = after;
+  }
+  world.insert(grid);
+
+  let entities: Vec<_> = ratios
+    .iter()
+    .map(|ratio| {
+      let builder = world
+        .create_entity()
+        .with(ParticlePosition::new(Vector3f::new(0.4, 0.4, 0.4)))
+        .with(ParticleVelocity::new(velocity));
+      match ratio {
+        Some(ratio) => builder.with(ParticleFlipRatio::new(*ratio)).build(),
+        None => builder.build(),
+      }
+    })
+    .collect();
+  G2PSystem.run_now(&world);
+  let velocities = world.read_storage::<ParticleVelocity>();
+  entities.iter().map(|&e| velocities.get(e).unwrap().get()).collect()
+}
+
+#[test]
+fn particle_flip_ratio_overrides_world_ratio() {
+  let (velocity, before, after) = (
+    Vector3f::new(1.0, 2.0, 3.0),
+    Vector3f::new(0.5, 0.0, -1.0),
+    Vector3f::new(0.0, 1.0, 1.0),
+  );
+  let flip = velocity + after - before;
+  let world_ratio = FlipRatio::default().get();
+  let gathered = gather_velocities(velocity, before, after, &[None, Some(1.0), Some(0.3)]);
+  let expected = [
+    (1.0 - world_ratio) * after + world_ratio * flip,
+    flip,
+    0.7 * after + 0.3 * flip,
+  ];
+  for (v, e) in gathered.iter().zip(&expected) {
+    assert!((v - e).norm() < 1e-5, "Gathered {} instead of {}", v, e);
+  }
+}
+
+#[test]
+fn zero_flip_ratio_is_pure_pic() {
+  let (before, after) = (Vector3f::new(0.5, 0.0, -1.0), Vector3f::new(0.0, 1.0, 1.0));
+  for &velocity in &[Vector3f::zeros(), Vector3f::new(-4.0, 3.0, 2.0)] {
+    let gathered = gather_velocities(velocity, before, after, &[Some(0.0)]);
+
+    // The particle's own velocity is forgotten
+    assert!((gathered[0] - after).norm() < 1e-5, "Gathered {}", gathered[0]);
+  }
+}
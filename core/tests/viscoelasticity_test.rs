@@ -0,0 +1,84 @@
+use mpm_rs::*;
+use specs::prelude::*;
+
+/// The deviatoric part of the log of the singular values
+fn deviatoric_strain(sigma: &Vector3f) -> Vector3f {
+  let eps = sigma.map(Float::ln);
+  eps.add_scalar(-eps.sum() / 3.0)
+}
+
+#[test]
+fn relax_keeps_volume() {
+  let visco = ParticleViscoelasticity::new(0.1);
+  let sigma = Vector3f::new(1.3, 0.9, 0.7);
+  let relaxed = visco.relax(&sigma, 0.05);
+  assert!((relaxed.map(Float::ln).sum() - sigma.map(Float::ln).sum()).abs() < 1e-5);
+
+  // The deviatoric strain decays exponentially
+  let factor = (-0.05 / 0.1 as Float).exp();
+  assert!((deviatoric_strain(&relaxed) - factor * deviatoric_strain(&sigma)).norm() < 1e-5);
+}
+
+#[test]
+fn relax_limits() {
+  let sigma = Vector3f::new(1.3, 0.9, 0.7);
+  let visco = ParticleViscoelasticity::new(0.1);
+  assert!((visco.relax(&sigma, 0.0) - sigma).norm() < 1e-5);
+
+  // Without relaxation time, only the volume change is left
+  let relaxed = ParticleViscoelasticity::new(0.0).relax(&sigma, 0.01);
+  assert!(deviatoric_strain(&relaxed).norm() < 1e-5);
+  assert!((relaxed.map(Float::ln).sum() - sigma.map(Float::ln).sum()).abs() < 1e-5);
+}
+
+/// Let a free square of viscoelastic material flow for `steps` steps, starting with the shear
+/// flow $v_x = \dot\gamma (y - y_c)$. Returns the shear rate fitted from the particle velocities
+fn sheared_block(viscosity: Float, shear_rate: Float, steps: usize) -> Float {
+  let mut world = WorldBuilder::new()
+    .with_size_2d(Vector2f::new(0.6, 0.6))
+    .with_dx(0.04)
+    .with_dt(0.001)
+    .with_transfer_scheme(TransferScheme::Apic)
+    .build();
+  world
+    .put_rectangle(Vector2f::new(0.22, 0.22), Vector2f::new(0.38, 0.38), 1.0)
+    .with(ParticleDeformation::elastic(1000.0, 0.3))
+    .with(ParticleViscoelasticity::new(10.0).with_viscosity(viscosity));
+  {
+    let (positions, mut velocities, mut affines): (
+      ReadStorage<ParticlePosition>,
+      WriteStorage<ParticleVelocity>,
+      WriteStorage<ParticleAffineVelocity>,
+    ) = world.world.system_data();
+    for (x, v, c) in (&positions, &mut velocities, &mut affines).join() {
+      v.set(Vector3f::new(shear_rate * (x.get().y - 0.3), 0.0, 0.0));
+      let mut grad_v = Matrix3f::zeros();
+      grad_v[(0, 1)] = shear_rate;
+      c.set(grad_v);
+    }
+  }
+  for _ in 0..steps {
+    world.step();
+  }
+
+  // Least squares fit of the velocity along x against y
+  let (positions, velocities): (ReadStorage<ParticlePosition>, ReadStorage<ParticleVelocity>) =
+    world.world.system_data();
+  let (mut n, mut sy, mut sv, mut syy, mut syv) = (0.0, 0.0, 0.0, 0.0, 0.0);
+  for (x, v) in (&positions, &velocities).join() {
+    let (y, u) = (x.get().y, v.get().x);
+    n += 1.0;
+    sy += y;
+    sv += u;
+    syy += y * y;
+    syv += y * u;
+  }
+  (n * syv - sy * sv) / (n * syy - sy * sy)
+}
+
+#[test]
+fn viscous_stress_damps_motion() {
+  let (elastic, viscous) = (sheared_block(0.0, 2.0, 20), sheared_block(50.0, 2.0, 20));
+  assert!(elastic > 1.9, "Shear rate went down to {} without viscosity", elastic);
+  assert!(viscous < 1.75, "Viscous shear rate stayed at {}", viscous);
+}
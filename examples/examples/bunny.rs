@@ -10,6 +10,7 @@ fn main() {
       num_cycles: 5000,
       dump_skip: 20,
       world_dt: 0.001,
+      transfer_scheme: TransferScheme::Apic,
       ..Default::default()
     },
     |world| {
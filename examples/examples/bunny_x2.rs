@@ -11,6 +11,7 @@ fn main() {
       num_cycles: 5000,
       dump_skip: 20,
       world_dt: 0.001,
+      contact_friction: Some(0.3),
       ..Default::default()
     },
     |world| {
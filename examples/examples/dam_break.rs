@@ -0,0 +1,27 @@
+use mpm_rs::*;
+use mpm_examples::*;
+
+fn main() {
+  run_example(
+    Config {
+      output_directory: "result/dam_break",
+      world_size: Vector3f::new(1.0, 0.5, 0.3),
+      world_dt: 0.0005,
+      transfer_scheme: TransferScheme::Apic,
+      num_cycles: 90,
+      frame_dt: Some(1.0 / 60.0),
+      ..Default::default()
+    },
+    |world| {
+      // Put the boundary
+      world.put_sliding_boundary(0.04);
+
+      // Put a column of water at one end of the tank
+      let (min, max) = (Vector3f::new(0.04, 0.04, 0.04), Vector3f::new(0.3, 0.4, 0.26));
+      let size = max - min;
+      world
+        .put_cube(min, max, 1000.0 * size.x * size.y * size.z)
+        .with(ParticleFluid::water());
+    }
+  )
+}
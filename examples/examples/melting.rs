@@ -0,0 +1,31 @@
+use mpm_rs::*;
+use mpm_examples::*;
+
+fn main() {
+  run_example(
+    Config {
+      output_directory: "result/melting",
+      world_size: Vector3f::new(0.6, 0.4, 0.6),
+      world_dt: 0.0005,
+      transfer_scheme: TransferScheme::Apic,
+      num_cycles: 8000,
+      dump_skip: 40,
+      ..Default::default()
+    },
+    |world| {
+      // Put the boundary, with a hot floor
+      world.put_sticky_boundary(0.04);
+      world.put_wrapping_temperature_boundary(0.04, |wall| match wall {
+        Wall::Bottom => Some(50.0),
+        _ => None,
+      });
+
+      // Put a cold block of wax which melts from the bottom
+      world
+        .put_cube(Vector3f::new(0.2, 0.04, 0.2), Vector3f::new(0.4, 0.24, 0.4), 3.0)
+        .with(ParticleDeformation::elastic(20000.0, 0.3))
+        .with(ParticleTemperature::new(-10.0, 1.0, 1.0))
+        .with(ParticleMelting::new(0.0, 20.0));
+    }
+  )
+}
@@ -4,8 +4,8 @@ use pbr::ProgressBar;
 
 struct Ball {
   center: Vector3f,
-  radius: f32,
-  mass: f32,
+  radius: Float,
+  mass: Float,
 }
 
 fn main() {
@@ -6,8 +6,8 @@ use nalgebra as na;
 struct Ball {
   center: Vector3f,
   velocity: Vector3f,
-  radius: f32,
-  mass: f32,
+  radius: Float,
+  mass: Float,
   color: Color,
 }
 
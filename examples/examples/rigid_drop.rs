@@ -0,0 +1,43 @@
+use mpm_examples::*;
+use mpm_rs::*;
+use nalgebra as na;
+
+fn main() {
+  run_example(
+    Config {
+      output_directory: "result/rigid_drop",
+      world_size: Vector3f::new(1.0, 0.6, 1.0),
+      world_dt: 0.0002,
+      transfer_scheme: TransferScheme::Apic,
+      contact_friction: Some(0.4),
+      num_cycles: 8000,
+      dump_skip: 40,
+      ..Default::default()
+    },
+    |world| {
+      // Put the boundary
+      world.put_friction_boundary(0.04, 0.5);
+
+      // A bed of sand on one side and a bed of snow on the other
+      world
+        .put_cube(Vector3f::new(0.04, 0.04, 0.04), Vector3f::new(0.5, 0.2, 0.96), 40.0)
+        .with(ParticleDeformation::elastic(353700.0, 0.3))
+        .with(ParticleConstitutiveModel::new(Hencky))
+        .with(ParticlePlasticity::drucker_prager(35.0));
+      world
+        .put_cube(Vector3f::new(0.5, 0.04, 0.04), Vector3f::new(0.96, 0.2, 0.96), 20.0)
+        .with(ParticleDeformation::snow());
+
+      // Drop a tilted box into the sand and a ball into the snow
+      let rotation = na::UnitQuaternion::from_euler_angles(0.3, 0.0, 0.4);
+      let transf = na::Similarity3::from_parts(na::Translation3::new(0.27, 0.4, 0.5), rotation, 1.0);
+      world.put_rigid_body(Cube::new(Vector3f::new(0.12, 0.12, 0.12)), transf, 10.0);
+      let transf = na::Similarity3::from_parts(
+        na::Translation3::new(0.73, 0.4, 0.5),
+        na::UnitQuaternion::identity(),
+        1.0,
+      );
+      world.put_rigid_body(Sphere::new(0.07), transf, 5.0);
+    },
+  )
+}
@@ -7,6 +7,7 @@ fn main() {
     Config {
       world_size: Vector3f::new(10.0, 1.0, 1.0),
       world_dt: 0.001,
+      transfer_scheme: TransferScheme::Apic,
       output_directory: "result/rolling_snowball",
       num_cycles: 1000,
       dump_skip: 10,
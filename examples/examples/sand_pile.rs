@@ -0,0 +1,28 @@
+use mpm_rs::*;
+use mpm_examples::*;
+
+fn main() {
+  run_example(
+    Config {
+      output_directory: "result/sand_pile",
+      world_size: Vector3f::new(1.0, 0.5, 1.0),
+      world_dt: 0.0002,
+      transfer_scheme: TransferScheme::Apic,
+      num_cycles: 8000,
+      dump_skip: 40,
+      ..Default::default()
+    },
+    |world| {
+      // Put the boundary
+      world.put_friction_boundary(0.04, 0.5);
+
+      // Put a column of sand collapsing into a pile. The slope of the pile
+      // should follow the friction angle
+      world
+        .put_cube(Vector3f::new(0.4, 0.04, 0.4), Vector3f::new(0.6, 0.34, 0.6), 20.0)
+        .with(ParticleDeformation::elastic(353700.0, 0.3))
+        .with(ParticleConstitutiveModel::new(Hencky))
+        .with(ParticlePlasticity::drucker_prager(35.0));
+    }
+  )
+}
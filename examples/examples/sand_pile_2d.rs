@@ -0,0 +1,42 @@
+use mpm_rs::*;
+use mpm_examples::*;
+
+fn main() {
+  run_example(
+    Config {
+      output_directory: "result/sand_pile_2d",
+      world_size: Vector3f::new(1.0, 0.5, 0.0),
+      planar: true,
+      world_dx: 0.01,
+      world_dt: 0.0002,
+      transfer_scheme: TransferScheme::Apic,
+      num_cycles: 8000,
+      dump_skip: 40,
+      ..Default::default()
+    },
+    |world| {
+      // Put the boundary
+      world.put_friction_boundary(0.03, 0.5);
+
+      // Put a column of sand collapsing into a pile
+      world
+        .put_rectangle(Vector2f::new(0.15, 0.03), Vector2f::new(0.35, 0.33), 1.0)
+        .with(ParticleDeformation::elastic(353700.0, 0.3))
+        .with(ParticleConstitutiveModel::new(Hencky))
+        .with(ParticlePlasticity::drucker_prager(35.0));
+
+      // Drop a jelly triangle and a ball on the other side
+      let triangle = [
+        Vector2f::new(0.6, 0.1),
+        Vector2f::new(0.8, 0.1),
+        Vector2f::new(0.7, 0.27),
+      ];
+      world
+        .put_polygon(&triangle, 0.3)
+        .with(ParticleDeformation::elastic(20000.0, 0.3));
+      world
+        .put_circle(Vector2f::new(0.7, 0.38), 0.06, 0.2)
+        .with(ParticleDeformation::elastic(20000.0, 0.3));
+    }
+  )
+}
@@ -0,0 +1,26 @@
+use mpm_rs::*;
+use mpm_examples::*;
+
+fn main() {
+  run_example(
+    Config {
+      output_directory: "result/slime",
+      world_size: Vector3f::new(0.6, 0.4, 0.6),
+      world_dt: 0.0005,
+      transfer_scheme: TransferScheme::Apic,
+      num_cycles: 10000,
+      dump_skip: 40,
+      ..Default::default()
+    },
+    |world| {
+      // Put the boundary
+      world.put_friction_boundary(0.04, 0.5);
+
+      // Put a viscoelastic blob which bounces first and then slowly spreads on the floor
+      world
+        .put_ball(Vector3f::new(0.3, 0.2, 0.3), 0.08, 2.0)
+        .with(ParticleDeformation::elastic(20000.0, 0.3))
+        .with(ParticleViscoelasticity::new(0.3).with_viscosity(5.0));
+    }
+  )
+}
@@ -8,22 +8,37 @@ use std::time::SystemTime;
 #[derive(Debug, Clone)]
 pub struct Config<'a> {
   pub world_size: Vector3f,
-  pub world_dx: f32,
-  pub world_dt: f32,
+  /// Simulate a 2D world of the x and y of `world_size`
+  pub planar: bool,
+  pub world_dx: Float,
+  pub world_dt: Float,
+  pub time_step: TimeStep,
+  pub transfer_scheme: TransferScheme,
+  pub contact_friction: Option<Float>,
+  pub integrator: Integrator,
   pub output_directory: &'a str,
   pub num_cycles: u64,
   pub dump_skip: usize,
+  /// Advance by frames of `frame_dt` instead of single steps. `num_cycles` then counts frames
+  /// and a file is dumped at the end of every frame
+  pub frame_dt: Option<Float>,
 }
 
 impl<'a> Default for Config<'a> {
   fn default() -> Self {
     Self {
       world_size: Vector3f::new(1.0, 1.0, 1.0),
+      planar: false,
       world_dx: 0.02,
       world_dt: 0.01,
+      time_step: TimeStep::Fixed,
+      transfer_scheme: TransferScheme::PicFlip,
+      contact_friction: None,
+      integrator: Integrator::Explicit,
       output_directory: "result",
       num_cycles: 500,
       dump_skip: 10,
+      frame_dt: None,
     }
   }
 }
@@ -46,10 +61,20 @@ where
     .get_matches();
 
   // Get basic world builder
-  let world_builder = WorldBuilder::new()
-    .with_size(config.world_size)
+  let world_builder = if config.planar {
+    WorldBuilder::new().with_size_2d(config.world_size.xy())
+  } else {
+    WorldBuilder::new().with_size(config.world_size)
+  };
+  let mut world_builder = world_builder
     .with_dx(config.world_dx)
-    .with_dt(config.world_dt);
+    .with_dt(config.world_dt)
+    .with_time_step(config.time_step)
+    .with_transfer_scheme(config.transfer_scheme)
+    .with_integrator(config.integrator);
+  if let Some(friction) = config.contact_friction {
+    world_builder = world_builder.with_body_contact(friction);
+  }
 
   // Build the world
   let mut world = (if matches.is_present("view") {
@@ -63,9 +88,15 @@ where
         "[DEBUG] Enabling dumping result. Output to '{}'",
         config.output_directory
       );
-      println!("[DEBUG] Setting dump skip {}", config.dump_skip);
+      match config.frame_dt {
+        Some(frame_dt) => println!("[DEBUG] Dumping every frame of {}", frame_dt),
+        None => println!("[DEBUG] Setting dump skip {}", config.dump_skip),
+      }
     }
-    let dump_sys = PlyDumpSystem::new(config.output_directory, config.dump_skip);
+    let dump_sys = match config.frame_dt {
+      Some(_) => PlyDumpSystem::per_frame(config.output_directory),
+      None => PlyDumpSystem::new(config.output_directory, config.dump_skip),
+    };
     world_builder.with_system(dump_sys)
   })
   .build();
@@ -89,7 +120,7 @@ where
       println!("[DEBUG] Starting viewer...");
     }
     while world.not_ending() {
-      world.step();
+      advance(&mut world, config.frame_dt);
     }
   } else {
     let start = SystemTime::now();
@@ -100,7 +131,7 @@ where
     let mut pb = ProgressBar::new(config.num_cycles);
     for _ in 0..config.num_cycles {
       pb.inc();
-      world.step();
+      advance(&mut world, config.frame_dt);
     }
     let finish = if matches.is_present("time") {
       let secs_elapsed = start.elapsed().unwrap().as_secs();
@@ -111,3 +142,13 @@ where
     pb.finish_print(finish.as_str());
   }
 }
+
+/// Advance the world by a frame when `frame_dt` is given, otherwise by a single step
+fn advance(world: &mut World, frame_dt: Option<Float>) {
+  match frame_dt {
+    Some(frame_dt) => {
+      world.advance_frame(frame_dt);
+    }
+    None => world.step(),
+  }
+}
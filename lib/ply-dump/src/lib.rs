@@ -10,15 +10,25 @@ use mpm_rs::resources::*;
 pub struct PlyDumpSystem {
   out_dir: String,
   dump_count: usize,
-  dump_skip: usize,
+  dump_skip: Option<usize>,
 }
 
 impl PlyDumpSystem {
+  /// Dump every `dump_skip` steps
   pub fn new(out_dir: &str, dump_skip: usize) -> Self {
     Self {
       out_dir: String::from(out_dir),
       dump_count: 0,
-      dump_skip,
+      dump_skip: Some(dump_skip),
+    }
+  }
+
+  /// Dump at the end of every frame advanced by `World::advance_frame`
+  pub fn per_frame(out_dir: &str) -> Self {
+    Self {
+      out_dir: String::from(out_dir),
+      dump_count: 0,
+      dump_skip: None,
     }
   }
 }
@@ -26,22 +36,63 @@ impl PlyDumpSystem {
 impl<'a> System<'a> for PlyDumpSystem {
   type SystemData = (
     Read<'a, StepCount>,
+    Read<'a, SimulationTime>,
+    Read<'a, Grid>,
     ReadStorage<'a, ParticlePosition>,
     ReadStorage<'a, Hidden>,
+    ReadStorage<'a, ParticleDamage>,
+    ReadStorage<'a, RigidBody>,
   );
 
-  fn run(&mut self, (step_count, positions, hiddens): Self::SystemData) {
-    if step_count.get() % self.dump_skip == 0 {
+  fn run(&mut self, (step_count, time, grid, positions, hiddens, damages, rigid_bodies): Self::SystemData) {
+    let should_dump = match self.dump_skip {
+      Some(dump_skip) => step_count.get() % dump_skip == 0,
+      None => time.is_frame_end(),
+    };
+    if should_dump {
       self.dump_count += 1;
-      let filename = format!("{}/{}.poly", self.out_dir, self.dump_count);
-      let mut file = File::create(filename).unwrap();
-      file.write(b"POINTS\n").unwrap();
-      for (i, (pos, _)) in (&positions, !&hiddens).join().enumerate() {
-        let p = pos.get();
-        let line = format!("{}: {} {} {}\n", i + 1, p.x, p.y, p.z);
-        file.write(line.as_bytes()).unwrap();
+      if grid.planar {
+        // 2D worlds only dump the x and y of the points
+        let filename = format!("{}/{}.xy", self.out_dir, self.dump_count);
+        let mut file = File::create(filename).unwrap();
+        for (i, (pos, _)) in (&positions, !&hiddens).join().enumerate() {
+          let p = pos.get();
+          let line = format!("{}: {} {}\n", i + 1, p.x, p.y);
+          file.write(line.as_bytes()).unwrap();
+        }
+      } else {
+        let filename = format!("{}/{}.poly", self.out_dir, self.dump_count);
+        let mut file = File::create(filename).unwrap();
+        file.write(b"POINTS\n").unwrap();
+        for (i, (pos, _)) in (&positions, !&hiddens).join().enumerate() {
+          let p = pos.get();
+          let line = format!("{}: {} {} {}\n", i + 1, p.x, p.y, p.z);
+          file.write(line.as_bytes()).unwrap();
+        }
+        file.write(b"POLYS\nEND\n").unwrap();
+      }
+
+      // Dump the damage of the same points alongside, 0 for particles without damage
+      if damages.join().next().is_some() {
+        let filename = format!("{}/{}.damage", self.out_dir, self.dump_count);
+        let mut file = File::create(filename).unwrap();
+        for (i, (_, _, damage)) in (&positions, !&hiddens, damages.maybe()).join().enumerate() {
+          let d = damage.map_or(0.0, ParticleDamage::get);
+          let line = format!("{}: {}\n", i + 1, d);
+          file.write(line.as_bytes()).unwrap();
+        }
+      }
+
+      // Dump the position and orientation of the rigid bodies
+      if rigid_bodies.join().next().is_some() {
+        let filename = format!("{}/{}.rigid", self.out_dir, self.dump_count);
+        let mut file = File::create(filename).unwrap();
+        for (i, rigid) in rigid_bodies.join().enumerate() {
+          let (p, q) = (rigid.position, rigid.orientation.quaternion());
+          let line = format!("{}: {} {} {} {} {} {} {}\n", i + 1, p.x, p.y, p.z, q.w, q.i, q.j, q.k);
+          file.write(line.as_bytes()).unwrap();
+        }
       }
-      file.write(b"POLYS\nEND\n").unwrap();
     }
   }
 }
@@ -0,0 +1,30 @@
+use mpm_ply_dump::*;
+use mpm_rs::*;
+use specs::prelude::*;
+use std::fs;
+
+#[test]
+fn dump_damage() {
+  let outdir = std::env::temp_dir().join(format!("mpm-ply-dump-{}", std::process::id()));
+  fs::create_dir_all(&outdir).unwrap();
+  let mut world = WorldBuilder::new()
+    .with_size(Vector3f::new(0.2, 0.2, 0.2))
+    .with_dx(0.02)
+    .with_system(PlyDumpSystem::new(outdir.to_str().unwrap(), 1))
+    .build();
+
+  // Only some particles carry damage, and hidden particles are skipped
+  let mut damaged = ParticleDamage::brittle(DamageCriterion::Strain(0.1));
+  damaged.damage = 0.5;
+  world.put_particle(Vector3f::new(0.08, 0.1, 0.1), 0.001);
+  world.put_particle(Vector3f::new(0.1, 0.1, 0.1), 0.001).with(damaged);
+  let hidden = world.put_particle(Vector3f::new(0.12, 0.1, 0.1), 0.001).first();
+  world.world.write_storage::<Hidden>().insert(hidden, Hidden).unwrap();
+  world.step();
+
+  let points = fs::read_to_string(outdir.join("1.poly")).unwrap();
+  let damages = fs::read_to_string(outdir.join("1.damage")).unwrap();
+  fs::remove_dir_all(&outdir).unwrap();
+  assert_eq!(points.lines().filter(|line| line.contains(':')).count(), 2);
+  assert_eq!(damages, "1: 0\n2: 0.5\n");
+}
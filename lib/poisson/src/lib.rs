@@ -196,7 +196,7 @@ where
     // First compute dx and dim
     let n = dim::<D>() as f64;
     let grid_dx = r / N::from_f64(n.sqrt()).unwrap();
-    let grid_dim: VectorN<usize, D> = size.map(|l| NumCast::from(l / grid_dx).unwrap());
+    let grid_dim: VectorN<usize, D> = size.map(|l| NumCast::from(Float::ceil(l / grid_dx)).unwrap());
 
     // Generate grid cells
     let mut num_cells = 1;
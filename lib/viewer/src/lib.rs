@@ -88,20 +88,20 @@ impl<'a> System<'a> for WindowSystem {
   );
 
   fn run(&mut self, (entities, mut ending, grid, poses, colors, hiddens): Self::SystemData) {
-    let size = grid.size();
+    let (size, origin) = (grid.size(), grid.origin);
 
     // Change the size of the floor
     self.state.floor.set_local_scale(size.x, 0.01, size.z);
 
-    // Store the offset
-    let offset = Vector3f::new(-size.x / 2.0, 0.0, -size.z / 2.0);
+    // Store the offset putting the center of the floor at the origin
+    let offset = Vector3f::new(-origin.x - size.x / 2.0, -origin.y, -origin.z - size.z / 2.0);
 
     // First construct points
     let mut ps = vec![];
     let mut cs = vec![];
     for (ent, ParticlePosition(pos), _) in (&entities, &poses, !&hiddens).join() {
       let pos = pos + offset;
-      ps.push(Point3::new(pos.x, pos.y, pos.z));
+      ps.push(Point3::new(pos.x as f32, pos.y as f32, pos.z as f32));
       cs.push(match colors.get(ent) {
         Some(ParticleColor(c)) => c.clone(),
         _ => DEFAULT_COLOR,